@@ -5,17 +5,29 @@ in vec3 v_position;
 
 out vec4 color;
 
-uniform vec3 u_light;
+// directions towards the lights and their colors, see renderer::MAX_LIGHTS
+const int MAX_LIGHTS = 4;
+uniform int u_light_count;
+uniform vec3 u_lights[MAX_LIGHTS];
+uniform vec3 u_light_colors[MAX_LIGHTS];
 uniform vec3 u_color;
-
-const vec3 specular_color = vec3(1.0, 1.0, 1.0);
+uniform vec3 u_ambient;
+uniform vec3 u_specular;
+uniform float u_shininess;
+uniform vec3 u_emissive;
 
 void main() {
-    vec3 ambient_color = vec3(u_color.x / 2.0, u_color.y / 2.0, u_color.z / 2.0);
+    vec3 ambient_color = u_ambient * u_color / 2.0;
 
-    float diffuse = max(dot(normalize(v_normal), normalize(u_light)), 0.0);
+    vec3 normal = normalize(v_normal);
     vec3 camera_dir = normalize(-v_position);
-    vec3 half_direction = normalize(normalize(u_light) + camera_dir);
-    float specular = pow(max(dot(half_direction, normalize(v_normal)), 0.0), 16.0);
-    color = vec4(ambient_color + diffuse * u_color + specular * specular_color, 1.0);
+    vec3 lit = vec3(0.0);
+    for (int i = 0; i < u_light_count; i++) {
+        vec3 light_dir = normalize(u_lights[i]);
+        float diffuse = max(dot(normal, light_dir), 0.0);
+        vec3 half_direction = normalize(light_dir + camera_dir);
+        float specular = pow(max(dot(half_direction, normal), 0.0), u_shininess);
+        lit += u_light_colors[i] * (diffuse * u_color + specular * u_specular);
+    }
+    color = vec4(ambient_color + lit + u_emissive, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+#version 150
+
+in vec3 v_normal;
+in vec3 v_tangent;
+in vec3 v_bitangent;
+in vec3 v_position;
+in vec2 v_uv;
+
+out vec4 color;
+
+// directions towards the lights and their colors, see renderer::MAX_LIGHTS
+const int MAX_LIGHTS = 4;
+uniform int u_light_count;
+uniform vec3 u_lights[MAX_LIGHTS];
+uniform vec3 u_light_colors[MAX_LIGHTS];
+uniform vec3 u_color;
+uniform vec3 u_ambient;
+uniform vec3 u_specular;
+uniform float u_shininess;
+uniform vec3 u_emissive;
+
+uniform bool u_has_albedo_map;
+uniform sampler2D u_albedo_map;
+uniform bool u_has_normal_map;
+uniform sampler2D u_normal_map;
+uniform bool u_has_roughness_metallic_map;
+uniform sampler2D u_roughness_metallic_map;
+uniform bool u_has_emissive_map;
+uniform sampler2D u_emissive_map;
+
+void main() {
+    // the tangent frame is not orthonormalized after interpolation, as mikktspace expects
+    vec3 normal = normalize(v_normal);
+    if (u_has_normal_map) {
+        vec3 tangent_normal = texture(u_normal_map, v_uv).rgb * 2.0 - 1.0;
+        normal = normalize(tangent_normal.x * v_tangent + tangent_normal.y * v_bitangent + tangent_normal.z * v_normal);
+    }
+    vec3 albedo = u_color;
+    if (u_has_albedo_map) {
+        albedo *= texture(u_albedo_map, v_uv).rgb;
+    }
+    float shininess = u_shininess;
+    vec3 specular_color = u_specular;
+    if (u_has_roughness_metallic_map) {
+        float roughness = texture(u_roughness_metallic_map, v_uv).g;
+        shininess = mix(64.0, 4.0, roughness);
+        specular_color *= 1.0 - roughness;
+    }
+    // scaled by u_emissive, scene materials that only set the map get white
+    vec3 emissive = u_emissive;
+    if (u_has_emissive_map) {
+        emissive *= texture(u_emissive_map, v_uv).rgb;
+    }
+
+    vec3 ambient_color = u_ambient * albedo / 2.0;
+
+    vec3 camera_dir = normalize(-v_position);
+    vec3 lit = vec3(0.0);
+    for (int i = 0; i < u_light_count; i++) {
+        vec3 light_dir = normalize(u_lights[i]);
+        float diffuse = max(dot(normal, light_dir), 0.0);
+        vec3 half_direction = normalize(light_dir + camera_dir);
+        float specular = pow(max(dot(half_direction, normal), 0.0), shininess);
+        lit += u_light_colors[i] * (diffuse * albedo + specular * specular_color);
+    }
+    color = vec4(ambient_color + lit + emissive, 1.0);
+}
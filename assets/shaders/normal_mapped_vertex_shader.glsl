@@ -0,0 +1,28 @@
+#version 150
+
+in vec3 position;
+in vec3 normal;
+in vec2 uv;
+in vec4 tangent;
+
+out vec3 v_normal;
+out vec3 v_tangent;
+out vec3 v_bitangent;
+out vec3 v_position;
+out vec2 v_uv;
+
+uniform mat4 perspective;
+uniform mat4 view;
+uniform mat4 model;
+
+void main() {
+    mat4 modelview = view * model;
+    mat3 normal_matrix = transpose(inverse(mat3(modelview)));
+    v_normal = normal_matrix * normal;
+    v_tangent = mat3(modelview) * tangent.xyz;
+    // w is the mikktspace bitangent sign
+    v_bitangent = cross(v_normal, v_tangent) * tangent.w;
+    v_uv = uv;
+    gl_Position = perspective * modelview * vec4(position, 1.0);
+    v_position = gl_Position.xyz / gl_Position.w;
+}
@@ -0,0 +1,35 @@
+#version 150
+
+in vec3 v_normal;
+in vec3 v_position;
+in vec4 v_color;
+
+out vec4 color;
+
+// directions towards the lights and their colors, see renderer::MAX_LIGHTS
+const int MAX_LIGHTS = 4;
+uniform int u_light_count;
+uniform vec3 u_lights[MAX_LIGHTS];
+uniform vec3 u_light_colors[MAX_LIGHTS];
+uniform vec3 u_color;
+uniform vec3 u_ambient;
+uniform vec3 u_specular;
+uniform float u_shininess;
+uniform vec3 u_emissive;
+
+void main() {
+    vec3 albedo = u_color * v_color.rgb;
+    vec3 ambient_color = u_ambient * albedo / 2.0;
+
+    vec3 normal = normalize(v_normal);
+    vec3 camera_dir = normalize(-v_position);
+    vec3 lit = vec3(0.0);
+    for (int i = 0; i < u_light_count; i++) {
+        vec3 light_dir = normalize(u_lights[i]);
+        float diffuse = max(dot(normal, light_dir), 0.0);
+        vec3 half_direction = normalize(light_dir + camera_dir);
+        float specular = pow(max(dot(half_direction, normal), 0.0), u_shininess);
+        lit += u_light_colors[i] * (diffuse * albedo + specular * u_specular);
+    }
+    color = vec4(ambient_color + lit + u_emissive, 1.0);
+}
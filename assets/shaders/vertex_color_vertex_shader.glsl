@@ -0,0 +1,21 @@
+#version 150
+
+in vec3 position;
+in vec3 normal;
+in vec4 color;
+
+out vec3 v_normal;
+out vec3 v_position;
+out vec4 v_color;
+
+uniform mat4 perspective;
+uniform mat4 view;
+uniform mat4 model;
+
+void main() {
+    mat4 modelview = view * model;
+    v_normal = transpose(inverse(mat3(modelview))) * normal;
+    v_color = color;
+    gl_Position = perspective * modelview * vec4(position, 1.0);
+    v_position = gl_Position.xyz / gl_Position.w;
+}
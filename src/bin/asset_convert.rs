@@ -0,0 +1,315 @@
+// converts models into the mesh cache format and checks the references between assets,
+// runs without a window
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use game_engine_glium::game_engine::asset_manager::{find_material_library, join_relative};
+use game_engine_glium::game_engine::error::EngineError;
+use game_engine_glium::game_engine::gltf_import;
+use game_engine_glium::game_engine::mesh::MeshData;
+use game_engine_glium::game_engine::mesh_cache::{self, MeshFile, NamedMesh};
+use game_engine_glium::game_engine::mtl;
+use game_engine_glium::game_engine::normals::NormalOptions;
+use game_engine_glium::game_engine::scene;
+
+const USAGE: &str = "usage: asset_convert [--root <dir>] [--out <dir>] [--check] [--flat] [--crease <degrees>] [files...]
+
+converts obj, stl and ply files into <out>/<file>.mesh, the cache the engine reads them from,
+prints mesh statistics and checks material, texture and scene references.
+without files every model and scene under the root is processed.
+
+  --root <dir>        asset root, default assets
+  --out <dir>         cache directory, default <root>/.cache
+  --check             only print and check, write nothing
+  --flat              flat normals for models without normals
+  --crease <degrees>  smoothing crease angle";
+
+const MODEL_EXTENSIONS: [&str; 5] = ["obj", "stl", "ply", "gltf", "glb"];
+
+struct Options {
+    root: PathBuf,
+    out: Option<PathBuf>,
+    check: bool,
+    normals: NormalOptions,
+    files: Vec<String>,
+}
+
+fn main() {
+    let options = match parse_arguments(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let files = if options.files.is_empty() {
+        let mut files = Vec::new();
+        if let Err(e) = collect_files(&options.root, "", &mut files) {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+        files
+    } else {
+        options.files.clone()
+    };
+
+    let mut problems = 0;
+    for file in &files {
+        problems += process_file(&options, file);
+    }
+    println!("{} files, {} problems", files.len(), problems);
+    if problems > 0 {
+        process::exit(1);
+    }
+}
+
+fn parse_arguments(mut arguments: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        root: PathBuf::from("assets"),
+        out: None,
+        check: false,
+        normals: NormalOptions::default(),
+        files: Vec::new(),
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| arguments.next().ok_or(format!("{} needs a value", name));
+        match argument.as_str() {
+            "--root" => options.root = PathBuf::from(value("--root")?),
+            "--out" => options.out = Some(PathBuf::from(value("--out")?)),
+            "--check" => options.check = true,
+            "--flat" => options.normals.smooth = false,
+            "--crease" => {
+                let degrees = value("--crease")?.parse::<f32>().map_err(|e| format!("--crease: {}", e))?;
+                options.normals.crease_angle = degrees.to_radians();
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            file => options.files.push(file.replace('\\', "/")),
+        }
+    }
+    if options.out.is_none() {
+        options.out = Some(options.root.join(".cache"));
+    }
+    Ok(options)
+}
+
+// models and scenes below directory, relative to root. hidden directories like the cache are skipped
+fn collect_files(root: &Path, directory: &str, files: &mut Vec<String>) -> Result<(), EngineError> {
+    let path = root.join(directory);
+    let entries = fs::read_dir(&path).map_err(|e| EngineError::io(&path, e))?;
+    let mut entries = entries.filter_map(|e| e.ok()).collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let relative = if directory.is_empty() { name.clone() } else { format!("{}/{}", directory, name) };
+        if entry.path().is_dir() {
+            collect_files(root, &relative, files)?;
+        } else if extension(&relative) == "ron" || MODEL_EXTENSIONS.contains(&extension(&relative).as_str()) {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+// number of problems found
+fn process_file(options: &Options, file: &str) -> usize {
+    let result = match extension(file).as_str() {
+        "ron" => check_scene(options, file),
+        "gltf" | "glb" => print_gltf(options, file).map(|_| Vec::new()),
+        _ => convert_model(options, file),
+    };
+    match result {
+        Ok(problems) => {
+            for problem in &problems {
+                println!("  problem: {}", problem);
+            }
+            problems.len()
+        },
+        Err(e) => {
+            println!("{}\n  error: {}", file, e);
+            1
+        },
+    }
+}
+
+fn convert_model(options: &Options, file: &str) -> Result<Vec<String>, EngineError> {
+    let cache = if options.check { None } else { options.out.as_deref() };
+    let model = mesh_cache::load(&options.root, file, cache, &options.normals)?;
+    match cache {
+        Some(cache) => println!("{} -> {}", file, mesh_cache::cache_file(cache, file).display()),
+        None => println!("{}", file),
+    }
+    for mesh in &model.meshes {
+        print_mesh(&mesh.name, &mesh.data, &mesh.materials);
+    }
+    Ok(check_materials(options, file, &model))
+}
+
+// gltf files keep their textures and nodes so they are not cached, only described
+fn print_gltf(options: &Options, file: &str) -> Result<(), EngineError> {
+    let path = options.root.join(file);
+    let model = gltf_import::import(&path)?;
+    println!("{}", file);
+    for object in &model.meshes {
+        let data = MeshData::from_object(object, &[], &options.normals).map_err(|e| e.with_path(&path))?;
+        let materials = object.geometry.iter().map(|g| g.material_name.clone()).collect::<Vec<_>>();
+        print_mesh(&object.name, &data, &materials);
+    }
+    println!("  {} materials, {} images, {} root nodes", model.materials.len(), model.images.len(), model.nodes.len());
+    Ok(())
+}
+
+fn print_mesh(name: &str, data: &MeshData, materials: &[Option<String>]) {
+    let (min, max) = (data.bounds.min, data.bounds.max);
+    println!("  {}: {} vertices ({:?}{}), {} triangles, {} surfaces", name, data.vertices.len(), data.vertices.format(),
+        if data.colors.is_empty() { "" } else { " + colors" }, data.triangle_count(), data.surfaces.len());
+    println!("    bounds ({}, {}, {}) to ({}, {}, {})", min.x, min.y, min.z, max.x, max.y, max.z);
+    let names = materials.iter().map(|m| m.as_deref().unwrap_or("-")).collect::<Vec<_>>();
+    if !names.is_empty() {
+        println!("    materials {}", names.join(", "));
+    }
+}
+
+// the mtl library must exist and define every material the meshes use,
+// and the maps of those materials must exist
+fn check_materials(options: &Options, file: &str, model: &MeshFile) -> Vec<String> {
+    // meshes often share materials, each is checked once
+    let used = model.meshes.iter().flat_map(|m: &NamedMesh| m.materials.iter().flatten()).collect::<BTreeSet<_>>();
+    let names = used.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+    let library = match find_material_library(&options.root, file, model.library.as_deref(), &names) {
+        Some(library) => library,
+        None if used.is_empty() => return Vec::new(),
+        None => return vec!("materials are used but there is no mtl library".to_string()),
+    };
+    if model.library.as_deref() != Some(library.as_str()) {
+        println!("  warning: using material library {}", library);
+    }
+    let path = options.root.join(&library);
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => return vec!(EngineError::io(&path, e).to_string()),
+    };
+    let materials = match mtl::parse(&source) {
+        Ok(materials) => materials,
+        Err((line, message)) => return vec!(EngineError::MtlParse { path, line, message }.to_string()),
+    };
+
+    let mut problems = Vec::new();
+    for name in used {
+        match materials.iter().find(|m| &m.name == name) {
+            Some(material) => for map in material.diffuse_map.iter().chain(material.bump_map.iter()) {
+                let texture = join_relative(&library, map);
+                if !options.root.join(&texture).is_file() {
+                    problems.push(format!("{} of material '{}' does not exist", texture, name));
+                }
+            },
+            None => problems.push(format!("{} has no material '{}'", library, name)),
+        }
+    }
+    problems
+}
+
+fn check_scene(options: &Options, file: &str) -> Result<Vec<String>, EngineError> {
+    let path = options.root.join(file);
+    let source = fs::read_to_string(&path).map_err(|e| EngineError::io(&path, e))?;
+    let references = scene::scene_references(&source).map_err(|e| e.with_path(&path))?;
+    println!("{}\n  {} referenced files", file, references.len());
+    Ok(references.iter()
+        .filter(|reference| !options.root.join(reference).is_file())
+        .map(|reference| format!("{} does not exist", reference))
+        .collect())
+}
+
+fn extension(file: &str) -> String {
+    Path::new(file).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_engine_glium::game_engine::primitives;
+
+    fn arguments(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(|a| a.to_string())
+    }
+
+    // empty directory below the system temp directory, removed by the test
+    fn temp_root(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("asset_convert_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn parse_arguments_reads_options_and_files() {
+        let options = parse_arguments(arguments("models\\pawn.obj")).unwrap();
+        assert_eq!(options.root, PathBuf::from("assets"));
+        assert_eq!(options.out, Some(PathBuf::from("assets").join(".cache")));
+        assert_eq!(options.files, vec!("models/pawn.obj"));
+        assert!(!options.check && options.normals.smooth);
+
+        let options = parse_arguments(arguments("--root art --out build --check --flat --crease 90 a.obj b.ply")).unwrap();
+        assert_eq!((options.root, options.out), (PathBuf::from("art"), Some(PathBuf::from("build"))));
+        assert!(options.check && !options.normals.smooth);
+        assert!((options.normals.crease_angle - std::f32::consts::FRAC_PI_2).abs() < 1.0e-6);
+        assert_eq!(options.files, vec!("a.obj", "b.ply"));
+
+        assert_eq!(parse_arguments(arguments("--root")).err().unwrap(), "--root needs a value");
+        assert!(parse_arguments(arguments("--crease steep")).is_err());
+        assert_eq!(parse_arguments(arguments("--fast")).err().unwrap(), "unknown option --fast");
+    }
+
+    #[test]
+    fn collect_files_finds_models_and_scenes() {
+        let root = temp_root("collect");
+        for file in ["models/pawn.obj", "models/board.mtl", "scans/bust.PLY", "scenes/chess.ron", "notes.txt", ".cache/pawn.obj.mesh"].iter() {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let mut files = Vec::new();
+        collect_files(&root, "", &mut files).unwrap();
+        assert_eq!(files, vec!("models/pawn.obj", "scans/bust.PLY", "scenes/chess.ron"));
+        assert!(collect_files(&root, "missing", &mut files).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn check_materials_reports_each_problem_once() {
+        let root = temp_root("materials");
+        fs::write(root.join("set.mtl"), "newmtl white\nmap_Kd white.png\nnewmtl black\nmap_Kd black.png\n").unwrap();
+        fs::write(root.join("black.png"), "").unwrap();
+        let options = parse_arguments(arguments(&format!("--root {}", root.display()))).unwrap();
+        let mesh = |materials: &[&str]| NamedMesh {
+            name: "piece".to_string(),
+            data: MeshData::from_object(&primitives::cube(1.0, 1), &[], &NormalOptions::default()).unwrap(),
+            materials: materials.iter().map(|m| Some(m.to_string())).collect(),
+        };
+        let mut model = MeshFile {
+            library: Some("set.mtl".to_string()),
+            meshes: vec!(mesh(&["white", "red"]), mesh(&["black", "red", "white"])),
+        };
+        assert_eq!(check_materials(&options, "piece.obj", &model), vec!(
+            "set.mtl has no material 'red'".to_string(),
+            "white.png of material 'white' does not exist".to_string(),
+        ));
+
+        model.library = None;
+        assert_eq!(check_materials(&options, "piece.obj", &model), vec!("materials are used but there is no mtl library".to_string()));
+        model.library = Some("missing.mtl".to_string());
+        assert_eq!(check_materials(&options, "piece.obj", &model).len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
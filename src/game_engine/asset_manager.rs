@@ -0,0 +1,759 @@
+use glium::{Display, Program};
+use glium::texture::{RawImage2d, SrgbTexture2d, Texture2d};
+use wavefront_obj::obj;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::game_engine::color::Color;
+use crate::game_engine::error::EngineError;
+use crate::game_engine::gltf_import::{self, GltfMaterial, GltfModel, ModelNode, TextureRef};
+use crate::game_engine::material::Material;
+use crate::game_engine::mesh::{GpuMesh, MeshData};
+use crate::game_engine::mesh_cache;
+use crate::game_engine::mtl::{self, MtlMaterial};
+use crate::game_engine::normals::NormalOptions;
+use crate::game_engine::object3d::Object3D;
+use crate::game_engine::texture::{Sampler, Texture, TextureSlot};
+use crate::game_engine::transform::Transform;
+use crate::game_engine::vertex_types::VertexFormat;
+
+pub const DEFAULT_VERTEX_SHADER: &str = "shaders/vertex_shader.glsl";
+pub const DEFAULT_FRAGMENT_SHADER: &str = "shaders/fragment_shader.glsl";
+pub const TEXTURED_VERTEX_SHADER: &str = "shaders/textured_vertex_shader.glsl";
+pub const TEXTURED_FRAGMENT_SHADER: &str = "shaders/textured_fragment_shader.glsl";
+pub const NORMAL_MAPPED_VERTEX_SHADER: &str = "shaders/normal_mapped_vertex_shader.glsl";
+pub const NORMAL_MAPPED_FRAGMENT_SHADER: &str = "shaders/normal_mapped_fragment_shader.glsl";
+pub const VERTEX_COLOR_VERTEX_SHADER: &str = "shaders/vertex_color_vertex_shader.glsl";
+pub const VERTEX_COLOR_FRAGMENT_SHADER: &str = "shaders/vertex_color_fragment_shader.glsl";
+
+// index into one of the AssetManager storages, only valid for the manager that created it
+pub struct Handle<T> {
+    index: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize) -> Handle<T> {
+        Handle { index, marker: PhantomData }
+    }
+}
+
+// implemented by hand, derive would require T to implement them too
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+// loaded assets of one type together with the keys they were loaded from
+struct Storage<K, T> {
+    items: Vec<Rc<T>>,
+    keys: Vec<K>,
+    lookup: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, T> Storage<K, T> {
+    fn new() -> Storage<K, T> {
+        Storage {
+            items: Vec::new(),
+            keys: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+
+    fn find(&self, key: &K) -> Option<Handle<T>> {
+        self.lookup.get(key).map(|&i| Handle::new(i))
+    }
+
+    // replaces the asset if the key is already used, old handles then point to the new one
+    fn insert(&mut self, key: K, item: T) -> Handle<T> {
+        if let Some(&index) = self.lookup.get(&key) {
+            self.items[index] = Rc::new(item);
+            return Handle::new(index);
+        }
+        self.items.push(Rc::new(item));
+        self.keys.push(key.clone());
+        self.lookup.insert(key, self.items.len() - 1);
+        Handle::new(self.items.len() - 1)
+    }
+
+    fn get(&self, handle: Handle<T>) -> Rc<T> {
+        self.items[handle.index].clone()
+    }
+
+    // reverse lookup by identity, used to save references back to files
+    fn key_of(&self, item: &Rc<T>) -> Option<&K> {
+        let index = self.items.iter().position(|i| Rc::ptr_eq(i, item))?;
+        Some(&self.keys[index])
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+// loads every asset once, paths are relative to the asset root
+pub struct AssetManager {
+    display: Display,
+    root: PathBuf,
+    meshes: Storage<String, GpuMesh>,
+    shaders: Storage<(String, String), Program>,
+    materials: Storage<String, Material>,
+    // keyed by path and whether the texture is srgb
+    textures: Storage<(String, bool), Texture>,
+    mesh_materials: HashMap<Handle<GpuMesh>, MeshMaterials>,
+    model_files: HashMap<String, ModelFile>,
+    material_libraries: HashSet<String>,
+    normal_options: NormalOptions,
+    // off unless set_mesh_cache is called
+    mesh_cache: Option<PathBuf>,
+}
+
+// material references of an obj file, one name per surface
+struct MeshMaterials {
+    library: Option<String>,
+    names: Vec<Option<String>>,
+}
+
+// meshes of an obj or gltf file by name in file order, and the nodes placing them
+struct ModelFile {
+    meshes: Vec<(String, Handle<GpuMesh>)>,
+    nodes: Vec<ModelNode>,
+}
+
+impl AssetManager {
+    pub fn new(display: &Display, root: &str) -> AssetManager {
+        AssetManager {
+            display: display.clone(),
+            root: PathBuf::from(root),
+            meshes: Storage::new(),
+            shaders: Storage::new(),
+            materials: Storage::new(),
+            textures: Storage::new(),
+            mesh_materials: HashMap::new(),
+            model_files: HashMap::new(),
+            material_libraries: HashSet::new(),
+            normal_options: NormalOptions::default(),
+            mesh_cache: None,
+        }
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    pub fn read_to_string(&self, path: &str) -> Result<String, EngineError> {
+        let file = self.resolve(path);
+        fs::read_to_string(&file).map_err(|e| EngineError::io(&file, e))
+    }
+
+    // only affects meshes loaded afterwards, cached meshes are kept as they are
+    pub fn set_normal_options(&mut self, options: NormalOptions) {
+        self.normal_options = options;
+    }
+
+    // obj, stl and ply files are imported once and read from "<directory>/<path>.mesh" until they change.
+    // None turns the cache off again
+    pub fn set_mesh_cache(&mut self, directory: Option<PathBuf>) {
+        self.mesh_cache = directory;
+    }
+
+    // "file" is the first mesh of an obj or gltf file, "file#name" the mesh, object or group called name
+    pub fn load_mesh(&mut self, path: &str) -> Result<Handle<GpuMesh>, EngineError> {
+        if let Some(handle) = self.meshes.find(&path.to_string()) {
+            return Ok(handle);
+        }
+        let (file, name) = match path.find('#') {
+            Some(i) => (&path[..i], Some(&path[i + 1..])),
+            None => (path, None),
+        };
+        let meshes = self.load_model_file(file)?.meshes.clone();
+        let found = match name {
+            Some(name) => meshes.iter().find(|(mesh_name, _)| mesh_name == name),
+            None => meshes.first(),
+        };
+        found.map(|&(_, handle)| handle).ok_or_else(|| EngineError::ModelParse {
+            path: Some(self.resolve(file)),
+            message: match name {
+                Some(name) => format!("no mesh named '{}'", name),
+                None => "no meshes".to_string(),
+            },
+        })
+    }
+
+    // the root nodes of the file with their meshes and materials. obj files give one node
+    // per object or group, gltf files the node tree of their default scene
+    pub fn load_model(&mut self, path: &str) -> Result<Vec<Object3D>, EngineError> {
+        let model = self.load_model_file(path)?;
+        let (meshes, nodes) = (model.meshes.clone(), model.nodes.clone());
+        nodes.iter().map(|node| self.build_model_node(node, &meshes)).collect()
+    }
+
+    fn build_model_node(&mut self, node: &ModelNode, meshes: &[(String, Handle<GpuMesh>)]) -> Result<Object3D, EngineError> {
+        let mut object = Object3D::new(&node.name);
+        object.set_transform(node.transform);
+        if let Some(mesh_i) = node.mesh {
+            let handle = meshes[mesh_i].1;
+            object.mesh = Some(self.get_mesh(handle));
+            object.materials = self.load_mesh_materials(handle)?;
+        }
+        for child in &node.children {
+            let child = self.build_model_node(child, meshes)?;
+            object.add_child(child);
+        }
+        Ok(object)
+    }
+
+    // the format is picked by extension: .gltf and .glb, then .stl, .ply, .mesh and obj for everything else.
+    // everything but gltf goes through the mesh cache
+    fn load_model_file(&mut self, path: &str) -> Result<&ModelFile, EngineError> {
+        if !self.model_files.contains_key(path) {
+            let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            let model = match extension.as_str() {
+                "gltf" | "glb" => self.load_gltf(path)?,
+                _ => {
+                    let mesh_file = mesh_cache::load(&self.root, path, self.mesh_cache.as_deref(), &self.normal_options)?;
+                    let library = self.find_material_library(path, &mesh_file);
+                    let mut model = ModelFile { meshes: Vec::new(), nodes: Vec::new() };
+                    for (mesh_i, mesh) in mesh_file.meshes.iter().enumerate() {
+                        let handle = self.add_model_mesh(path, mesh_i, &mesh.name, &mesh.data,
+                            library.clone(), mesh.materials.clone())?;
+                        model.meshes.push((mesh.name.clone(), handle));
+                        model.nodes.push(ModelNode { name: mesh.name.clone(), transform: Transform::new(), mesh: Some(mesh_i), children: Vec::new() });
+                    }
+                    model
+                },
+            };
+            self.model_files.insert(path.to_string(), model);
+        }
+        Ok(&self.model_files[path])
+    }
+
+    // materials are registered as "file:name" like mtl materials, textures as "file#image<index>"
+    fn load_gltf(&mut self, path: &str) -> Result<ModelFile, EngineError> {
+        let model = gltf_import::import(&self.resolve(path))?;
+        for material in &model.materials {
+            let built = self.build_gltf_material(path, &model, material)?;
+            self.add_material(&material_key(path, &material.name), built);
+        }
+        // keeps load_mesh_materials from reading the file as an mtl library
+        self.material_libraries.insert(path.to_string());
+
+        let mut meshes = Vec::new();
+        for (object_i, object) in model.meshes.iter().enumerate() {
+            let data = MeshData::from_object(object, &[], &self.normal_options)
+                .map_err(|e| e.with_path(&self.resolve(path)))?;
+            let names = object.geometry.iter().map(|g| g.material_name.clone()).collect();
+            let handle = self.add_model_mesh(path, object_i, &object.name, &data, Some(path.to_string()), names)?;
+            meshes.push((object.name.clone(), handle));
+        }
+        Ok(ModelFile { meshes, nodes: model.nodes })
+    }
+
+    // warns when the library had to be searched for, the search can pick a library
+    // the model didn't name
+    fn find_material_library(&self, path: &str, mesh_file: &mesh_cache::MeshFile) -> Option<String> {
+        let mut used = mesh_file.meshes.iter().flat_map(|m| m.materials.iter().flatten().map(|n| n.as_str())).collect::<Vec<_>>();
+        used.sort_unstable();
+        used.dedup();
+        let named = mesh_file.library.as_deref();
+        let found = find_material_library(&self.root, path, named, &used);
+        if found.is_some() && found.as_deref() != named {
+            eprintln!("warning: {}: using material library {}", self.resolve(path).display(), found.as_deref().unwrap_or(""));
+        }
+        found
+    }
+
+    // the first mesh of a file is keyed by the plain path, the others by "path#name"
+    fn add_model_mesh(&mut self, path: &str, index: usize, name: &str, data: &MeshData, library: Option<String>,
+        names: Vec<Option<String>>) -> Result<Handle<GpuMesh>, EngineError> {
+        let mesh = GpuMesh::upload(data, &self.display).map_err(|e| e.with_path(&self.resolve(path)))?;
+        let key = if index == 0 { path.to_string() } else { format!("{}#{}", path, name) };
+        let handle = self.meshes.insert(key, mesh);
+        self.mesh_materials.insert(handle, MeshMaterials { library, names });
+        Ok(handle)
+    }
+
+    // one material per surface from the mtl library of the obj file,
+    // surfaces whose library or material is missing get the fallback material
+    pub fn load_mesh_materials(&mut self, mesh: Handle<GpuMesh>) -> Result<Vec<Material>, EngineError> {
+        let (library, names) = match self.mesh_materials.get(&mesh) {
+            Some(materials) => (materials.library.clone(), materials.names.clone()),
+            None => (None, Vec::new()),
+        };
+        if let Some(library) = &library {
+            if !self.material_libraries.contains(library) {
+                match self.load_material_library(library) {
+                    Err(e @ EngineError::Io { .. }) => eprintln!("warning: {}, using fallback materials", e),
+                    result => { result?; },
+                }
+                self.material_libraries.insert(library.clone());
+            }
+        }
+
+        let vertex_format = self.get_mesh(mesh).vertex_format;
+        let material_count = self.get_mesh(mesh).material_count;
+        let has_colors = self.get_mesh(mesh).color_buffer.is_some();
+        let mut materials = Vec::new();
+        for surface_i in 0..material_count {
+            let name = names.get(surface_i).cloned().flatten();
+            let handle = match (&library, name) {
+                (Some(library), Some(name)) => self.find_material(&material_key(library, &name)),
+                _ => None,
+            };
+            let mut material = match handle {
+                Some(handle) => (*self.get_material(handle)).clone(),
+                // vertex colors are shown as they are
+                None if has_colors => {
+                    let shader = self.load_shader(VERTEX_COLOR_VERTEX_SHADER, VERTEX_COLOR_FRAGMENT_SHADER)?;
+                    Material::new(Color::new(1.0, 1.0, 1.0, 1.0), self.get_shader(shader))
+                },
+                None => self.fallback_material()?,
+            };
+            // texture maps can't be sampled without uvs, normal maps also need tangents
+            let has_maps = material.albedo_map.is_some() || material.normal_map.is_some();
+            if vertex_format == VertexFormat::PositionNormal && has_maps {
+                let shader = self.load_shader(DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER)?;
+                material.albedo_map = None;
+                material.normal_map = None;
+                material.shader = self.get_shader(shader);
+            } else if vertex_format == VertexFormat::PositionNormalUv && material.normal_map.is_some() {
+                let shader = self.load_shader(TEXTURED_VERTEX_SHADER, TEXTURED_FRAGMENT_SHADER)?;
+                material.normal_map = None;
+                material.shader = self.get_shader(shader);
+            }
+            materials.push(material);
+        }
+        Ok(materials)
+    }
+
+    // registers every material of the library as "library:name"
+    pub fn load_material_library(&mut self, path: &str) -> Result<Vec<Handle<Material>>, EngineError> {
+        let source = self.read_to_string(path)?;
+        let file = self.resolve(path);
+        let descriptions = mtl::parse(&source)
+            .map_err(|(line, message)| EngineError::MtlParse { path: file.clone(), line, message })?;
+        let mut handles = Vec::new();
+        for description in &descriptions {
+            let material = self.build_mtl_material(path, description)?;
+            handles.push(self.add_material(&material_key(path, &description.name), material));
+        }
+        Ok(handles)
+    }
+
+    // used for surfaces without a usable mtl material
+    pub fn fallback_material(&mut self) -> Result<Material, EngineError> {
+        let shader = self.load_shader(DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER)?;
+        Ok(Material::new(Color::new(0.8, 0.8, 0.8, 1.0), self.get_shader(shader)))
+    }
+
+    // normal maps need the normal mapped shader, other maps the textured one
+    fn material_shader(&mut self, has_maps: bool, has_normal_map: bool) -> Result<Rc<Program>, EngineError> {
+        let shader = if has_normal_map {
+            self.load_shader(NORMAL_MAPPED_VERTEX_SHADER, NORMAL_MAPPED_FRAGMENT_SHADER)?
+        } else if has_maps {
+            self.load_shader(TEXTURED_VERTEX_SHADER, TEXTURED_FRAGMENT_SHADER)?
+        } else {
+            self.load_shader(DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER)?
+        };
+        Ok(self.get_shader(shader))
+    }
+
+    fn build_mtl_material(&mut self, library: &str, description: &MtlMaterial) -> Result<Material, EngineError> {
+        let shader = self.material_shader(description.diffuse_map.is_some(), description.bump_map.is_some())?;
+        let diffuse = description.diffuse;
+        let mut material = Material::new(Color::new(diffuse.r, diffuse.g, diffuse.b, description.dissolve), shader);
+        material.ambient = description.ambient;
+        material.specular = if description.has_specular() { description.specular } else { Color::new(0.0, 0.0, 0.0, 1.0) };
+        material.shininess = description.shininess;
+        material.emissive = description.emissive;
+        if let Some(map) = &description.diffuse_map {
+            let texture = self.load_texture(&join_relative(library, map))?;
+            material.albedo_map = Some(TextureSlot::new(self.get_texture(texture), Sampler::default()));
+        }
+        if let Some(map) = &description.bump_map {
+            let texture = self.load_linear_texture(&join_relative(library, map))?;
+            material.normal_map = Some(TextureSlot::new(self.get_texture(texture), Sampler::default()));
+        }
+        Ok(material)
+    }
+
+    fn build_gltf_material(&mut self, path: &str, model: &GltfModel, description: &GltfMaterial) -> Result<Material, EngineError> {
+        let has_maps = description.base_color_map.is_some() || description.metallic_roughness_map.is_some()
+            || description.emissive_map.is_some();
+        let shader = self.material_shader(has_maps, description.normal_map.is_some())?;
+        let [r, g, b, a] = description.base_color;
+        let mut material = Material::new(Color::new(r, g, b, a), shader);
+        let [r, g, b] = description.emissive;
+        material.emissive = Color::new(r, g, b, 1.0);
+        material.albedo_map = self.gltf_map(path, model, description.base_color_map, true)?;
+        material.normal_map = self.gltf_map(path, model, description.normal_map, false)?;
+        material.roughness_metallic_map = self.gltf_map(path, model, description.metallic_roughness_map, false)?;
+        material.emissive_map = self.gltf_map(path, model, description.emissive_map, true)?;
+        Ok(material)
+    }
+
+    fn gltf_map(&mut self, path: &str, model: &GltfModel, texture: Option<TextureRef>, srgb: bool)
+        -> Result<Option<TextureSlot>, EngineError> {
+        let texture = match texture {
+            Some(texture) => texture,
+            None => return Ok(None),
+        };
+        let image = &model.images[texture.image];
+        let handle = self.add_texture(&format!("{}#image{}", path, texture.image), image.pixels.clone(), (image.width, image.height), srgb)?;
+        Ok(Some(TextureSlot::new(self.get_texture(handle), texture.sampler)))
+    }
+
+    pub fn load_shader(&mut self, vertex: &str, fragment: &str) -> Result<Handle<Program>, EngineError> {
+        let key = (vertex.to_string(), fragment.to_string());
+        if let Some(handle) = self.shaders.find(&key) {
+            return Ok(handle);
+        }
+        let vertex_source = self.read_to_string(vertex)?;
+        let fragment_source = self.read_to_string(fragment)?;
+        let program = Program::from_source(&self.display, &vertex_source, &fragment_source, None)
+            .map_err(|error| EngineError::ShaderCompile { vertex: self.resolve(vertex), fragment: self.resolve(fragment), error })?;
+        Ok(self.shaders.insert(key, program))
+    }
+
+    // png or jpeg, for color maps
+    pub fn load_texture(&mut self, path: &str) -> Result<Handle<Texture>, EngineError> {
+        self.load_texture_as(path, true)
+    }
+
+    // png or jpeg, for maps that store data instead of colors
+    pub fn load_linear_texture(&mut self, path: &str) -> Result<Handle<Texture>, EngineError> {
+        self.load_texture_as(path, false)
+    }
+
+    // "file#image<index>" is an image embedded in or referenced by a gltf file
+    fn load_texture_as(&mut self, path: &str, srgb: bool) -> Result<Handle<Texture>, EngineError> {
+        let key = (path.to_string(), srgb);
+        if let Some(handle) = self.textures.find(&key) {
+            return Ok(handle);
+        }
+        let file = self.resolve(path);
+        if let Some(i) = path.find('#') {
+            self.load_model_file(&path[..i])?;
+            return self.textures.find(&key)
+                .ok_or_else(|| EngineError::Texture { path: file, message: "not used by the model".to_string() });
+        }
+        let image = image::open(&file)
+            .map_err(|e| EngineError::Texture { path: file.clone(), message: e.to_string() })?
+            .to_rgba8();
+        let dimensions = image.dimensions();
+        self.add_texture(path, image.into_raw(), dimensions, srgb)
+    }
+
+    // rgba pixels with the top row first, adding an existing key replaces the texture
+    pub fn add_texture(&mut self, key: &str, pixels: Vec<u8>, dimensions: (u32, u32), srgb: bool)
+        -> Result<Handle<Texture>, EngineError> {
+        let raw = RawImage2d::from_raw_rgba_reversed(&pixels, dimensions);
+        let texture = if srgb {
+            SrgbTexture2d::new(&self.display, raw).map(Texture::Srgb)
+        } else {
+            Texture2d::new(&self.display, raw).map(Texture::Linear)
+        };
+        let texture = texture.map_err(|e| EngineError::Texture { path: self.resolve(key), message: e.to_string() })?;
+        Ok(self.textures.insert((key.to_string(), srgb), texture))
+    }
+
+    // meshes built in code like the primitives, load_mesh finds them by key.
+    // their surfaces get the fallback material
+    pub fn add_mesh(&mut self, key: &str, data: &MeshData) -> Result<Handle<GpuMesh>, EngineError> {
+        let mesh = GpuMesh::upload(data, &self.display)?;
+        let handle = self.meshes.insert(key.to_string(), mesh);
+        self.mesh_materials.remove(&handle);
+        Ok(handle)
+    }
+
+    // materials are registered by name, adding an existing name replaces it
+    pub fn add_material(&mut self, name: &str, material: Material) -> Handle<Material> {
+        self.materials.insert(name.to_string(), material)
+    }
+
+    pub fn find_mesh(&self, key: &str) -> Option<Handle<GpuMesh>> {
+        self.meshes.find(&key.to_string())
+    }
+
+    pub fn find_material(&self, name: &str) -> Option<Handle<Material>> {
+        self.materials.find(&name.to_string())
+    }
+
+    pub fn get_mesh(&self, handle: Handle<GpuMesh>) -> Rc<GpuMesh> {
+        self.meshes.get(handle)
+    }
+
+    pub fn get_shader(&self, handle: Handle<Program>) -> Rc<Program> {
+        self.shaders.get(handle)
+    }
+
+    pub fn get_material(&self, handle: Handle<Material>) -> Rc<Material> {
+        self.materials.get(handle)
+    }
+
+    pub fn get_texture(&self, handle: Handle<Texture>) -> Rc<Texture> {
+        self.textures.get(handle)
+    }
+
+    // path the mesh was loaded from
+    pub fn get_mesh_path(&self, mesh: &Rc<GpuMesh>) -> Option<&str> {
+        self.meshes.key_of(mesh).map(|k| k.as_str())
+    }
+
+    // vertex and fragment paths the shader was compiled from
+    pub fn get_shader_paths(&self, shader: &Rc<Program>) -> Option<(&str, &str)> {
+        self.shaders.key_of(shader).map(|(v, f)| (v.as_str(), f.as_str()))
+    }
+
+    pub fn get_texture_path(&self, texture: &Rc<Texture>) -> Option<&str> {
+        self.textures.key_of(texture).map(|(path, _)| path.as_str())
+    }
+
+    pub fn mesh_count(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+}
+
+// every object of the set, objects made of several `g` groups are split into one object per group.
+// names are made unique
+pub fn split_objects(object_set: &obj::ObjSet) -> Vec<obj::Object> {
+    let mut result = Vec::<obj::Object>::new();
+    for object in &object_set.objects {
+        let mut groups = Vec::<&str>::new();
+        for shape in object.geometry.iter().flat_map(|g| g.shapes.iter()) {
+            let group = shape.groups.first().map(|g| g.as_str()).unwrap_or("");
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        if groups.len() <= 1 {
+            result.push(object.clone());
+            continue;
+        }
+        for group in groups {
+            let mut part = object.clone();
+            part.name = if group.is_empty() { object.name.clone() } else { group.to_string() };
+            for geometry in part.geometry.iter_mut() {
+                geometry.shapes.retain(|s| s.groups.first().map(|g| g.as_str()).unwrap_or("") == group);
+            }
+            part.geometry.retain(|g| !g.shapes.is_empty());
+            result.push(part);
+        }
+    }
+
+    let names = unique_names(result.iter().map(|o| o.name.clone()));
+    for (object, name) in result.iter_mut().zip(names) {
+        object.name = name;
+    }
+    result
+}
+
+// names already used earlier in the list get .1, .2, ... appended
+pub fn unique_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut result = Vec::<String>::new();
+    for name in names {
+        let mut unique = name.clone();
+        let mut suffix = 1;
+        while result.contains(&unique) {
+            unique = format!("{}.{}", name, suffix);
+            suffix += 1;
+        }
+        result.push(unique);
+    }
+    result
+}
+
+fn material_key(library: &str, name: &str) -> String {
+    format!("{}:{}", library, name)
+}
+
+// path relative to the directory of file, both relative to the asset root
+pub fn join_relative(file: &str, path: &str) -> String {
+    let mut parts = file.split('/').collect::<Vec<&str>>();
+    parts.pop();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => (),
+            ".." => { parts.pop(); },
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+// the mtl library of a model, relative to the asset root. the library the obj names is used
+// if it exists, otherwise materials/ is searched for a file with the same name and then for
+// a library defining every material the model uses. objs without an mtllib line only get
+// materials/<model name>.mtl. a named library that isn't found is returned as it is
+// so loading it reports the missing file
+pub fn find_material_library(root: &Path, model: &str, library: Option<&str>, used: &[&str]) -> Option<String> {
+    let library = match library {
+        Some(library) => library,
+        None if used.is_empty() => return None,
+        None => {
+            let model_name = Path::new(model).file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let candidate = format!("materials/{}.mtl", model_name);
+            return if root.join(&candidate).is_file() { Some(candidate) } else { None };
+        },
+    };
+    if root.join(library).is_file() {
+        return Some(library.to_string());
+    }
+    let file_name = library.rsplit('/').next().unwrap_or(library);
+    let same_name = format!("materials/{}", file_name);
+    if root.join(&same_name).is_file() {
+        return Some(same_name);
+    }
+    let mut files = fs::read_dir(root.join("materials")).into_iter().flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".mtl"))
+        .collect::<Vec<_>>();
+    files.sort();
+    let defines_used = |file: &String| fs::read_to_string(root.join("materials").join(file)).ok()
+        .and_then(|source| mtl::parse(&source).ok())
+        .is_some_and(|materials| used.iter().all(|name| materials.iter().any(|m| &m.name == name)));
+    match files.iter().find(|file| !used.is_empty() && defines_used(file)) {
+        Some(file) => Some(format!("materials/{}", file)),
+        None => Some(library.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_returns_cached_handle() {
+        let mut storage = Storage::<String, u32>::new();
+        let first = storage.insert("models/pawn.obj".to_string(), 1);
+        let second = storage.insert("models/rook.obj".to_string(), 2);
+        assert_ne!(first, second);
+        assert_eq!(storage.find(&"models/pawn.obj".to_string()), Some(first));
+        assert_eq!(storage.find(&"models/king.obj".to_string()), None);
+        assert_eq!(*storage.get(second), 2);
+        assert_eq!(storage.len(), 2);
+    }
+
+    #[test]
+    fn storage_reverse_lookup() {
+        let mut storage = Storage::<String, u32>::new();
+        let handle = storage.insert("a".to_string(), 1);
+        let item = storage.get(handle);
+        assert_eq!(storage.key_of(&item).map(|k| k.as_str()), Some("a"));
+        assert_eq!(storage.key_of(&Rc::new(1)), None);
+
+        // same key replaces the asset but keeps the handle
+        assert_eq!(storage.insert("a".to_string(), 5), handle);
+        assert_eq!(*storage.get(handle), 5);
+        assert_eq!(storage.len(), 1);
+    }
+
+    #[test]
+    fn join_relative_resolves_parent_directories() {
+        assert_eq!(join_relative("models/rook.obj", "../materials/rook.mtl"), "materials/rook.mtl");
+        assert_eq!(join_relative("models/rook.obj", "rook.mtl"), "models/rook.mtl");
+        assert_eq!(join_relative("rook.obj", "./textures\\rook.png"), "textures/rook.png");
+    }
+
+    #[test]
+    fn split_objects_by_object_and_group() {
+        // face indices count vertices from the start of the file
+        let source = "
+            o pawn
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            f 1 2 3
+            o set
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            g king
+            f 4 5 6
+            g queen
+            f 4 5 6
+            f 6 5 4
+            o pawn
+            v 0 0 1
+            v 1 0 1
+            v 0 1 1
+            f 7 8 9
+        ";
+        let objects = split_objects(&obj::parse(source).unwrap());
+        let names = objects.iter().map(|o| o.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!("pawn", "king", "queen", "pawn.1"));
+        assert_eq!(objects[1].geometry[0].shapes.len(), 1);
+        assert_eq!(objects[2].geometry[0].shapes.len(), 2);
+    }
+
+    #[test]
+    fn material_libraries_fall_back_to_the_materials_directory() {
+        let root = Path::new("assets");
+        let find = |model, library, used: &[&str]| find_material_library(root, model, library, used);
+        assert_eq!(find("models/board.obj", Some("models/board.mtl"), &["Material.001"]).as_deref(), Some("materials/board.mtl"));
+        // pawn.mtl and queen.mtl define the same material, the first file that defines it is used
+        assert_eq!(find("models/pawn.obj", Some("models/white_pawn.mtl"), &["Material_003.001"]).as_deref(),
+            Some("materials/pawn.mtl"));
+        assert_eq!(find("models/rook.obj", Some("models/white_rook.mtl"), &["Material_003"]).as_deref(), Some("materials/rook.mtl"));
+        assert_eq!(find("models/rook.obj", Some("materials/king.mtl"), &["Material_003"]).as_deref(), Some("materials/king.mtl"));
+        // nothing defines it, the named library is kept
+        assert_eq!(find("models/rook.obj", Some("models/tower.mtl"), &["Stone"]).as_deref(), Some("models/tower.mtl"));
+        // the model name only counts without an mtllib line
+        assert_eq!(find("models/rook.obj", None, &["Material_003"]).as_deref(), Some("materials/rook.mtl"));
+        assert_eq!(find("models/tower.obj", None, &["Material_003"]), None);
+        assert_eq!(find("models/rook.obj", None, &[]), None);
+    }
+
+    #[test]
+    fn bundled_models_reference_existing_materials() {
+        for name in ["bishop", "board", "king", "knight", "pawn", "queen", "rook"].iter() {
+            let path = format!("models/{}.obj", name);
+            let object = obj::parse(fs::read_to_string(Path::new("assets").join(&path)).unwrap()).unwrap();
+            let library = join_relative(&path, object.material_library.as_ref().unwrap());
+            let used = object.objects[0].geometry.iter().filter_map(|g| g.material_name.as_deref()).collect::<Vec<_>>();
+            let library = find_material_library(Path::new("assets"), &path, Some(&library), &used).unwrap();
+            let materials = mtl::parse(&fs::read_to_string(Path::new("assets").join(&library)).unwrap()).unwrap();
+            for geometry in &object.objects[0].geometry {
+                let material_name = geometry.material_name.as_ref().unwrap();
+                assert!(materials.iter().any(|m| &m.name == material_name), "{} in {}", material_name, library);
+            }
+        }
+    }
+}
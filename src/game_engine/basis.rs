@@ -1,9 +1,20 @@
+use std::ops;
+
 use crate::game_engine::vector3::Vector3;
+use crate::game_engine::quaternion::Quaternion;
 
+// elements are the rows of the 3x3 matrix
+#[derive(Copy, Clone, Debug)]
 pub struct Basis {
     elements: [Vector3; 3],
 }
 
+impl Default for Basis {
+    fn default() -> Self {
+        Basis::new()
+    }
+}
+
 impl Basis {
     pub fn new() -> Basis {
         Basis {
@@ -15,6 +26,91 @@ impl Basis {
         }
     }
 
+    pub fn from_rows(x: Vector3, y: Vector3, z: Vector3) -> Basis {
+        Basis { elements: [x, y, z] }
+    }
+
+    pub fn from_axes(x: Vector3, y: Vector3, z: Vector3) -> Basis {
+        Basis::from_rows(
+            Vector3::new(x.x, y.x, z.x),
+            Vector3::new(x.y, y.y, z.y),
+            Vector3::new(x.z, y.z, z.z),
+        )
+    }
+
+    // Rodrigues' rotation formula, axis is expected to be normalized
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Basis {
+        let (s, c) = angle.sin_cos();
+        let t = 1.0 - c;
+        let Vector3 { x, y, z } = axis;
+        Basis::from_rows(
+            Vector3::new(c + x * x * t, x * y * t - z * s, x * z * t + y * s),
+            Vector3::new(x * y * t + z * s, c + y * y * t, y * z * t - x * s),
+            Vector3::new(x * z * t - y * s, y * z * t + x * s, c + z * z * t),
+        )
+    }
+
+    // euler angles in radians, applied in YXZ order
+    pub fn from_euler(euler: Vector3) -> Basis {
+        Basis::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), euler.y)
+            * Basis::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), euler.x)
+            * Basis::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), euler.z)
+    }
+
+    // local +Z points along direction, local +Y is as close to up as possible.
+    // a direction parallel to up keeps +Y towards whichever world axis is least parallel to it
+    pub fn looking_at(direction: Vector3, up: Vector3) -> Basis {
+        let front = direction.normalized();
+        let mut right = up.cross(front);
+        if right.length() <= 1.0e-6 * up.length() {
+            let fallback = if front.z.abs() < 0.9 { Vector3::new(0.0, 0.0, 1.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+            right = fallback.cross(front);
+        }
+        let right = right.normalized();
+        let up = front.cross(right);
+        Basis::from_axes(right, up, front)
+    }
+
+    pub fn get_axis(&self, axis: usize) -> Vector3 {
+        self.transposed().elements[axis]
+    }
+
+    pub fn transposed(&self) -> Basis {
+        Basis::from_axes(self.elements[0], self.elements[1], self.elements[2])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.elements[0].dot(self.elements[1].cross(self.elements[2]))
+    }
+
+    // None if the basis is singular, tiny scales are still inverted
+    pub fn inverse(&self) -> Option<Basis> {
+        let inv_det = 1.0 / self.determinant();
+        if !inv_det.is_finite() {
+            return None;
+        }
+        let [x, y, z] = self.elements;
+        Some(Basis::from_axes(y.cross(z) * inv_det, z.cross(x) * inv_det, x.cross(y) * inv_det))
+    }
+
+    // Gram-Schmidt on the axes
+    pub fn orthonormalized(&self) -> Basis {
+        let x = self.get_axis(0).normalized();
+        let y = self.get_axis(1);
+        let y = (y - x * x.dot(y)).normalized();
+        let z = self.get_axis(2);
+        let z = (z - x * x.dot(z) - y * y.dot(z)).normalized();
+        Basis::from_axes(x, y, z)
+    }
+
+    pub fn xform(&self, vector: Vector3) -> Vector3 {
+        Vector3::new(
+            self.elements[0].dot(vector),
+            self.elements[1].dot(vector),
+            self.elements[2].dot(vector),
+        )
+    }
+
     pub fn scale(&mut self, scale: Vector3) {
         self.elements[0] *= scale.x;
         self.elements[1] *= scale.y;
@@ -30,10 +126,213 @@ impl Basis {
     }
 
     pub fn rotate(&mut self, axis: Vector3, angle: f32) {
-        
+        *self = Basis::from_axis_angle(axis.normalized(), angle) * *self;
+    }
+
+    pub fn rotate_x(&mut self, angle: f32) {
+        self.rotate(Vector3::new(1.0, 0.0, 0.0), angle);
+    }
+
+    pub fn rotate_y(&mut self, angle: f32) {
+        self.rotate(Vector3::new(0.0, 1.0, 0.0), angle);
+    }
+
+    pub fn rotate_z(&mut self, angle: f32) {
+        self.rotate(Vector3::new(0.0, 0.0, 1.0), angle);
+    }
+
+    // keeps the current scale
+    pub fn set_rotation(&mut self, euler: Vector3) {
+        self.set_rotation_basis(Basis::from_euler(euler));
+    }
+
+    pub fn set_rotation_axis_angle(&mut self, axis: Vector3, angle: f32) {
+        self.set_rotation_basis(Basis::from_axis_angle(axis.normalized(), angle));
+    }
+
+    pub fn set_rotation_quaternion(&mut self, rotation: Quaternion) {
+        self.set_rotation_basis(rotation.normalized().to_basis());
+    }
+
+    pub fn get_rotation_quaternion(&self) -> Quaternion {
+        Quaternion::from_basis(self)
+    }
+
+    pub fn look_at(&mut self, direction: Vector3, up: Vector3) {
+        self.set_rotation_basis(Basis::looking_at(direction, up));
+    }
+
+    fn set_rotation_basis(&mut self, rotation: Basis) {
+        let scale = self.get_scale();
+        *self = rotation;
+        for row in self.elements.iter_mut() {
+            *row *= scale;
+        }
+    }
+
+    // pure rotation part of the basis, reflection is folded into the scale
+    pub fn get_rotation_basis(&self) -> Basis {
+        let mut rotation = self.orthonormalized();
+        if rotation.determinant() < 0.0 {
+            rotation.scale(Vector3::fill(-1.0));
+        }
+        rotation
+    }
+
+    // euler angles in radians, YXZ order
+    pub fn get_rotation(&self) -> Vector3 {
+        let m = self.get_rotation_basis().elements;
+        let m12 = m[1].z;
+        let epsilon = 1.0e-6;
+        if m12 < 1.0 - epsilon {
+            if m12 > -(1.0 - epsilon) {
+                Vector3::new(
+                    (-m12).asin(),
+                    m[0].z.atan2(m[2].z),
+                    m[1].x.atan2(m[1].y),
+                )
+            } else {
+                Vector3::new(std::f32::consts::FRAC_PI_2, m[0].y.atan2(m[0].x), 0.0)
+            }
+        } else {
+            Vector3::new(-std::f32::consts::FRAC_PI_2, -m[0].y.atan2(m[0].x), 0.0)
+        }
+    }
+
+    // returns normalized axis and angle in radians
+    pub fn get_rotation_axis_angle(&self) -> (Vector3, f32) {
+        let m = self.get_rotation_basis().elements;
+        let cos = ((m[0].x + m[1].y + m[2].z - 1.0) / 2.0).clamp(-1.0, 1.0);
+        let angle = cos.acos();
+        let epsilon = 1.0e-4;
+
+        if angle < epsilon {
+            return (Vector3::new(0.0, 1.0, 0.0), 0.0);
+        }
+
+        if std::f32::consts::PI - angle < epsilon {
+            // sin is close to zero, take the axis from the symmetric part
+            let xx = ((m[0].x + 1.0) / 2.0).max(0.0).sqrt();
+            let yy = ((m[1].y + 1.0) / 2.0).max(0.0).sqrt();
+            let zz = ((m[2].z + 1.0) / 2.0).max(0.0).sqrt();
+            let axis = if xx >= yy && xx >= zz {
+                Vector3::new(xx, m[0].y / (2.0 * xx), m[0].z / (2.0 * xx))
+            } else if yy >= zz {
+                Vector3::new(m[0].y / (2.0 * yy), yy, m[1].z / (2.0 * yy))
+            } else {
+                Vector3::new(m[0].z / (2.0 * zz), m[1].z / (2.0 * zz), zz)
+            };
+            return (axis.normalized(), angle);
+        }
+
+        let axis = Vector3::new(
+            m[2].y - m[1].z,
+            m[0].z - m[2].x,
+            m[1].x - m[0].y,
+        );
+        (axis.normalized(), angle)
     }
 
     pub fn get_elements(&self) -> &[Vector3; 3] {
         &self.elements
     }
-}
\ No newline at end of file
+}
+
+
+impl ops::Mul<Basis> for Basis {
+    type Output = Basis;
+    fn mul(self, rhs: Basis) -> Self::Output {
+        let row = |r: Vector3| rhs.elements[0] * r.x + rhs.elements[1] * r.y + rhs.elements[2] * r.z;
+        Basis::from_rows(row(self.elements[0]), row(self.elements[1]), row(self.elements[2]))
+    }
+} // Basis * Basis
+
+impl ops::Mul<Vector3> for Basis {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        self.xform(rhs)
+    }
+} // Basis * Vector3
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).length() < 1.0e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rotate_y_turns_x_into_negative_z() {
+        let mut basis = Basis::new();
+        basis.rotate_y(FRAC_PI_2);
+        assert_near(basis.xform(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(0.0, 0.0, -1.0));
+        assert_near(basis.get_axis(2), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn euler_round_trip() {
+        let euler = Vector3::new(0.3, -1.2, 0.7);
+        let mut basis = Basis::new();
+        basis.set_rotation(euler);
+        assert_near(basis.get_rotation(), euler);
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let axis = Vector3::new(1.0, 2.0, -0.5).normalized();
+        for &angle in &[0.4, 2.0, PI - 1.0e-5] {
+            let mut basis = Basis::new();
+            basis.rotate(axis, angle);
+            let (result_axis, result_angle) = basis.get_rotation_axis_angle();
+            assert!((result_angle - angle).abs() < 1.0e-3);
+            assert!((result_axis - axis).length() < 1.0e-2 || (result_axis + axis).length() < 1.0e-2);
+        }
+    }
+
+    #[test]
+    fn set_rotation_keeps_scale() {
+        let mut basis = Basis::new();
+        basis.scale(Vector3::new(2.0, 3.0, 4.0));
+        basis.set_rotation(Vector3::new(0.5, 0.5, 0.5));
+        assert_near(basis.get_scale(), Vector3::new(2.0, 3.0, 4.0));
+        assert_near(basis.get_rotation(), Vector3::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn inverse_undoes_xform() {
+        let mut basis = Basis::from_euler(Vector3::new(0.2, -0.8, 1.4));
+        basis.scale(Vector3::new(0.5, 2.0, 3.0));
+        let v = Vector3::new(1.0, 2.0, 3.0);
+        assert_near(basis.inverse().unwrap().xform(basis.xform(v)), v);
+        assert!(Basis::from_rows(Vector3::fill(1.0), Vector3::fill(1.0), Vector3::fill(0.0)).inverse().is_none());
+
+        let mut small = basis;
+        small.scale(Vector3::fill(0.004));
+        assert_near(small.inverse().unwrap().xform(small.xform(v)), v);
+    }
+
+    #[test]
+    fn looking_at_points_z_at_direction() {
+        let direction = Vector3::new(1.0, 0.5, -2.0);
+        let basis = Basis::looking_at(direction, Vector3::new(0.0, 1.0, 0.0));
+        assert_near(basis.get_axis(2), direction.normalized());
+        assert!(basis.get_axis(0).y.abs() < 1.0e-5);
+        assert!((basis.determinant() - 1.0).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn looking_at_along_up_stays_finite() {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        for &direction in &[Vector3::new(0.0, -3.0, 0.0), up, Vector3::new(0.0, 1.0e-9, 1.0e-3)] {
+            let basis = Basis::looking_at(direction, up);
+            assert_near(basis.get_axis(2), direction.normalized());
+            assert!((basis.determinant() - 1.0).abs() < 1.0e-5, "{:?}", direction);
+        }
+        let basis = Basis::looking_at(Vector3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_near(basis.get_axis(2), Vector3::new(0.0, 0.0, 1.0));
+        assert!((basis.determinant() - 1.0).abs() < 1.0e-5);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::game_engine::math;
+use crate::game_engine::matrix4::Matrix4;
+use crate::game_engine::transform::Transform;
+use crate::game_engine::vector3::Vector3;
+
+// looks along its local +Z axis
+pub struct Camera {
+    pub transform: Transform,
+    // vertical field of view in radians
+    pub fov: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            transform: Transform::new(),
+            fov: std::f32::consts::PI / 3.0,
+            znear: 0.1,
+            zfar: 1024.0,
+        }
+    }
+
+    pub fn get_direction(&self) -> Vector3 {
+        self.transform.basis_xform(Vector3::new(0.0, 0.0, 1.0)).normalized()
+    }
+
+    pub fn get_up(&self) -> Vector3 {
+        self.transform.basis_xform(Vector3::new(0.0, 1.0, 0.0)).normalized()
+    }
+
+    pub fn view_matrix(&self) -> Matrix4 {
+        math::view_matrix(self.transform.get_position(), self.get_direction(), self.get_up())
+    }
+
+    pub fn perspective_matrix(&self, frame_size: (u32, u32)) -> Matrix4 {
+        math::perspective_matrix(frame_size, self.fov, self.zfar, self.znear)
+    }
+}
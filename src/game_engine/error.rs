@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum EngineError {
+    Io { path: PathBuf, error: io::Error },
+    // path is filled in by with_path when the file was imported from bytes
+    ObjParse { path: Option<PathBuf>, line: usize, message: String },
+    MtlParse { path: PathBuf, line: usize, message: String },
+    // path is unknown when the mesh is built from an already parsed object
+    MissingAttribute { path: Option<PathBuf>, object: String, attribute: &'static str },
+    SceneParse { path: Option<PathBuf>, message: String },
+    // a scene that can't be written, like objects using resources that weren't loaded from a file
+    SceneSerialize { path: Option<PathBuf>, message: String },
+    // gltf, stl or ply files
+    ModelParse { path: Option<PathBuf>, message: String },
+    Texture { path: PathBuf, message: String },
+    ShaderCompile { vertex: PathBuf, fragment: PathBuf, error: glium::ProgramCreationError },
+    BufferCreation(String),
+    // more vertices than 32 bit indices can address
+    TooManyVertices(usize),
+    Draw(glium::DrawError),
+    SwapBuffers(glium::SwapBuffersError),
+}
+
+impl EngineError {
+    pub fn io(path: &Path, error: io::Error) -> EngineError {
+        EngineError::Io { path: path.to_path_buf(), error }
+    }
+
+    // fills in the file for errors created without one
+    pub fn with_path(self, file: &Path) -> EngineError {
+        match self {
+            EngineError::MissingAttribute { path: None, object, attribute } =>
+                EngineError::MissingAttribute { path: Some(file.to_path_buf()), object, attribute },
+            EngineError::ObjParse { path: None, line, message } =>
+                EngineError::ObjParse { path: Some(file.to_path_buf()), line, message },
+            EngineError::SceneParse { path: None, message } =>
+                EngineError::SceneParse { path: Some(file.to_path_buf()), message },
+            EngineError::SceneSerialize { path: None, message } =>
+                EngineError::SceneSerialize { path: Some(file.to_path_buf()), message },
+            EngineError::ModelParse { path: None, message } =>
+                EngineError::ModelParse { path: Some(file.to_path_buf()), message },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            EngineError::ObjParse { path: Some(path), line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            EngineError::ObjParse { path: None, line, message } => write!(f, "line {}: {}", line, message),
+            EngineError::MtlParse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            EngineError::MissingAttribute { path: Some(path), object, attribute } =>
+                write!(f, "{}: object '{}' has faces without {}", path.display(), object, attribute),
+            EngineError::MissingAttribute { path: None, object, attribute } =>
+                write!(f, "object '{}' has faces without {}", object, attribute),
+            EngineError::SceneParse { path: Some(path), message } => write!(f, "{}: {}", path.display(), message),
+            EngineError::SceneParse { path: None, message } => write!(f, "{}", message),
+            EngineError::SceneSerialize { path: Some(path), message } => write!(f, "{}: {}", path.display(), message),
+            EngineError::SceneSerialize { path: None, message } => write!(f, "{}", message),
+            EngineError::ModelParse { path: Some(path), message } => write!(f, "{}: {}", path.display(), message),
+            EngineError::ModelParse { path: None, message } => write!(f, "{}", message),
+            EngineError::Texture { path, message } => write!(f, "{}: {}", path.display(), message),
+            EngineError::ShaderCompile { vertex, fragment, error } =>
+                write!(f, "{} / {}: {}", vertex.display(), fragment.display(), error),
+            EngineError::BufferCreation(message) => write!(f, "buffer creation failed: {}", message),
+            EngineError::TooManyVertices(count) => write!(f, "{} vertices can't be addressed with 32 bit indices", count),
+            EngineError::Draw(error) => write!(f, "draw failed: {}", error),
+            EngineError::SwapBuffers(error) => write!(f, "swap buffers failed: {}", error),
+        }
+    }
+}
+
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EngineError::Io { error, .. } => Some(error),
+            EngineError::ShaderCompile { error, .. } => Some(error),
+            EngineError::Draw(error) => Some(error),
+            EngineError::SwapBuffers(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<glium::vertex::BufferCreationError> for EngineError {
+    fn from(error: glium::vertex::BufferCreationError) -> Self {
+        EngineError::BufferCreation(error.to_string())
+    }
+}
+
+impl From<glium::index::BufferCreationError> for EngineError {
+    fn from(error: glium::index::BufferCreationError) -> Self {
+        EngineError::BufferCreation(error.to_string())
+    }
+}
+
+impl From<glium::DrawError> for EngineError {
+    fn from(error: glium::DrawError) -> Self {
+        EngineError::Draw(error)
+    }
+}
+
+impl From<glium::SwapBuffersError> for EngineError {
+    fn from(error: glium::SwapBuffersError) -> Self {
+        EngineError::SwapBuffers(error)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_include_location() {
+        let error = EngineError::ObjParse { path: None, line: 12, message: "bad face".to_string() };
+        assert_eq!(error.to_string(), "line 12: bad face");
+        let error = error.with_path(Path::new("assets/models/pawn.obj"));
+        assert_eq!(error.to_string(), "assets/models/pawn.obj:12: bad face");
+
+        let error = EngineError::MissingAttribute { path: None, object: "Pawn".to_string(), attribute: "normals" };
+        assert_eq!(error.to_string(), "object 'Pawn' has faces without normals");
+        let error = error.with_path(Path::new("models/pawn.obj"));
+        assert_eq!(error.to_string(), "models/pawn.obj: object 'Pawn' has faces without normals");
+    }
+}
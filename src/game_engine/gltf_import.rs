@@ -0,0 +1,385 @@
+use gltf::mesh::Mode;
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+use wavefront_obj::obj;
+
+use std::path::Path;
+
+use crate::game_engine::asset_manager::unique_names;
+use crate::game_engine::basis::Basis;
+use crate::game_engine::error::EngineError;
+use crate::game_engine::texture::{Filter, Sampler, Wrap};
+use crate::game_engine::transform::Transform;
+use crate::game_engine::vector3::Vector3;
+
+// contents of a .gltf or .glb file. meshes are converted to obj objects so they are
+// built by MeshData::from_object like wavefront files, one geometry per primitive
+pub struct GltfModel {
+    pub meshes: Vec<obj::Object>,
+    pub materials: Vec<GltfMaterial>,
+    pub images: Vec<GltfImage>,
+    // root nodes of the default scene
+    pub nodes: Vec<ModelNode>,
+}
+
+// metallic and roughness factors are not kept, the shaders only read the map
+pub struct GltfMaterial {
+    // unique within the file, used as the material name of the geometries
+    pub name: String,
+    pub base_color: [f32; 4],
+    pub emissive: [f32; 3],
+    pub base_color_map: Option<TextureRef>,
+    pub normal_map: Option<TextureRef>,
+    pub metallic_roughness_map: Option<TextureRef>,
+    pub emissive_map: Option<TextureRef>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureRef {
+    // index into GltfModel::images
+    pub image: usize,
+    pub sampler: Sampler,
+}
+
+// rgba, rows from top to bottom
+pub struct GltfImage {
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+// node of an imported model, mesh is an index into the meshes of the file
+#[derive(Clone)]
+pub struct ModelNode {
+    pub name: String,
+    pub transform: Transform,
+    pub mesh: Option<usize>,
+    pub children: Vec<ModelNode>,
+}
+
+pub fn import(file: &Path) -> Result<GltfModel, EngineError> {
+    let (document, buffers, images) = gltf::import(file)
+        .map_err(|e| EngineError::ModelParse { path: Some(file.to_path_buf()), message: e.to_string() })?;
+    convert(&document, &buffers, images).map_err(|e| e.with_path(file))
+}
+
+fn convert(document: &gltf::Document, buffers: &[gltf::buffer::Data], images: Vec<gltf::image::Data>)
+    -> Result<GltfModel, EngineError> {
+
+    let material_names = unique_names(document.materials()
+        .map(|m| m.name().map(|n| n.to_string()).unwrap_or_else(|| format!("material{}", m.index().unwrap_or(0)))));
+    let materials = document.materials().zip(material_names.iter()).map(|(material, name)| {
+        let pbr = material.pbr_metallic_roughness();
+        GltfMaterial {
+            name: name.clone(),
+            base_color: pbr.base_color_factor(),
+            emissive: material.emissive_factor(),
+            base_color_map: pbr.base_color_texture().map(|info| texture_ref(&info.texture())),
+            normal_map: material.normal_texture().map(|info| texture_ref(&info.texture())),
+            metallic_roughness_map: pbr.metallic_roughness_texture().map(|info| texture_ref(&info.texture())),
+            emissive_map: material.emissive_texture().map(|info| texture_ref(&info.texture())),
+        }
+    }).collect();
+
+    let mesh_names = unique_names(document.meshes()
+        .map(|m| m.name().map(|n| n.to_string()).unwrap_or_else(|| format!("mesh{}", m.index()))));
+    let mut meshes = Vec::new();
+    for (mesh, name) in document.meshes().zip(mesh_names) {
+        meshes.push(convert_mesh(&mesh, name, &material_names, buffers)?);
+    }
+
+    let scene = document.default_scene().or_else(|| document.scenes().next());
+    let nodes = match scene {
+        Some(scene) => scene.nodes().map(|n| convert_node(&n, &meshes)).collect(),
+        None => Vec::new(),
+    };
+
+    Ok(GltfModel {
+        meshes,
+        materials,
+        images: images.into_iter().map(convert_image).collect(),
+        nodes,
+    })
+}
+
+fn convert_mesh(mesh: &gltf::Mesh, name: String, material_names: &[String], buffers: &[gltf::buffer::Data])
+    -> Result<obj::Object, EngineError> {
+
+    let mut object = obj::Object {
+        name,
+        vertices: Vec::new(),
+        tex_vertices: Vec::new(),
+        normals: Vec::new(),
+        geometry: Vec::new(),
+    };
+    for primitive in mesh.primitives() {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+        let positions = reader.read_positions().ok_or_else(|| EngineError::MissingAttribute {
+            path: None,
+            object: object.name.clone(),
+            attribute: "positions",
+        })?;
+
+        // attributes of every primitive are appended, missing normals are generated later
+        let first_vertex = object.vertices.len();
+        object.vertices.extend(positions.map(|p| obj::Vertex { x: p[0] as f64, y: p[1] as f64, z: p[2] as f64 }));
+        let vertex_count = object.vertices.len() - first_vertex;
+        let first_normal = object.normals.len();
+        let has_normals = match reader.read_normals() {
+            Some(normals) => {
+                object.normals.extend(normals.map(|n| obj::Vertex { x: n[0] as f64, y: n[1] as f64, z: n[2] as f64 }));
+                true
+            },
+            None => false,
+        };
+        // gltf uvs start at the top of the image, obj uvs at the bottom
+        let first_tex_vertex = object.tex_vertices.len();
+        let has_uvs = match reader.read_tex_coords(0) {
+            Some(uvs) => {
+                object.tex_vertices.extend(uvs.into_f32().map(|t| obj::TVertex { u: t[0] as f64, v: 1.0 - t[1] as f64, w: 0.0 }));
+                true
+            },
+            None => false,
+        };
+
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|i| i as usize).collect::<Vec<_>>(),
+            None => (0..vertex_count).collect(),
+        };
+        if let Some(&index) = indices.iter().find(|&&i| i >= vertex_count) {
+            return Err(EngineError::ModelParse {
+                path: None,
+                message: format!("mesh '{}' has index {} but {} vertices", object.name, index, vertex_count),
+            });
+        }
+        let corner = |i: usize| -> obj::VTNIndex {
+            (
+                first_vertex + i,
+                if has_uvs { Some(first_tex_vertex + i) } else { None },
+                if has_normals { Some(first_normal + i) } else { None },
+            )
+        };
+        let shapes = primitives(primitive.mode(), &indices).into_iter()
+            .map(|p| obj::Shape { primitive: map_corners(p, corner), groups: Vec::new(), smoothing_groups: Vec::new() })
+            .collect();
+        object.geometry.push(obj::Geometry {
+            material_name: primitive.material().index().map(|i| material_names[i].clone()),
+            shapes,
+        });
+    }
+    Ok(object)
+}
+
+// strips, loops and fans are turned into lists, corners are still plain indices
+fn primitives(mode: Mode, indices: &[usize]) -> Vec<obj::Primitive> {
+    let v = |i: usize| (i, None, None);
+    match mode {
+        Mode::Points => indices.iter().map(|&i| obj::Primitive::Point(v(i))).collect(),
+        Mode::Lines => indices.chunks_exact(2).map(|l| obj::Primitive::Line(v(l[0]), v(l[1]))).collect(),
+        Mode::LineStrip | Mode::LineLoop => {
+            let mut lines = indices.windows(2).map(|l| obj::Primitive::Line(v(l[0]), v(l[1]))).collect::<Vec<_>>();
+            if mode == Mode::LineLoop && indices.len() > 2 {
+                lines.push(obj::Primitive::Line(v(indices[indices.len() - 1]), v(indices[0])));
+            }
+            lines
+        },
+        Mode::Triangles => indices.chunks_exact(3).map(|t| obj::Primitive::Triangle(v(t[0]), v(t[1]), v(t[2]))).collect(),
+        // every other triangle of a strip is flipped to keep the winding
+        Mode::TriangleStrip => indices.windows(3).enumerate().map(|(i, t)| if i % 2 == 0 {
+            obj::Primitive::Triangle(v(t[0]), v(t[1]), v(t[2]))
+        } else {
+            obj::Primitive::Triangle(v(t[1]), v(t[0]), v(t[2]))
+        }).collect(),
+        Mode::TriangleFan => match indices.split_first() {
+            Some((&center, rest)) => rest.windows(2).map(|t| obj::Primitive::Triangle(v(center), v(t[0]), v(t[1]))).collect(),
+            None => Vec::new(),
+        },
+    }
+}
+
+fn map_corners(primitive: obj::Primitive, corner: impl Fn(usize) -> obj::VTNIndex) -> obj::Primitive {
+    match primitive {
+        obj::Primitive::Point(a) => obj::Primitive::Point(corner(a.0)),
+        obj::Primitive::Line(a, b) => obj::Primitive::Line(corner(a.0), corner(b.0)),
+        obj::Primitive::Triangle(a, b, c) => obj::Primitive::Triangle(corner(a.0), corner(b.0), corner(c.0)),
+    }
+}
+
+fn convert_node(node: &gltf::Node, meshes: &[obj::Object]) -> ModelNode {
+    // columns of the local matrix
+    let m = node.transform().matrix();
+    let column = |i: usize| Vector3::new(m[i][0], m[i][1], m[i][2]);
+    let mesh = node.mesh().map(|mesh| mesh.index());
+    let name = match (node.name(), mesh) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(mesh)) => meshes[mesh].name.clone(),
+        (None, None) => format!("node{}", node.index()),
+    };
+    ModelNode {
+        name,
+        transform: Transform::from_basis_origin(Basis::from_axes(column(0), column(1), column(2)), column(3)),
+        mesh,
+        children: node.children().map(|child| convert_node(&child, meshes)).collect(),
+    }
+}
+
+fn texture_ref(texture: &gltf::Texture) -> TextureRef {
+    let sampler = texture.sampler();
+    let mipmaps = !matches!(sampler.min_filter(), Some(MinFilter::Nearest) | Some(MinFilter::Linear));
+    TextureRef {
+        image: texture.source().index(),
+        sampler: Sampler {
+            wrap: match sampler.wrap_s() {
+                WrappingMode::Repeat => Wrap::Repeat,
+                WrappingMode::MirroredRepeat => Wrap::Mirror,
+                WrappingMode::ClampToEdge => Wrap::Clamp,
+            },
+            filter: match sampler.mag_filter() {
+                Some(MagFilter::Nearest) => Filter::Nearest,
+                _ => Filter::Linear,
+            },
+            mipmaps,
+            ..Sampler::default()
+        },
+    }
+}
+
+// expands every pixel format to 8 bit rgba, 16 bit channels keep the high byte
+fn convert_image(image: gltf::image::Data) -> GltfImage {
+    use gltf::image::Format;
+    let (channels, bytes_per_channel) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 | Format::B8G8R8 => (3, 1),
+        Format::R8G8B8A8 | Format::B8G8R8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+    };
+    let bgr = image.format == Format::B8G8R8 || image.format == Format::B8G8R8A8;
+    let mut pixels = Vec::with_capacity(image.width as usize * image.height as usize * 4);
+    for pixel in image.pixels.chunks_exact(channels * bytes_per_channel) {
+        // little endian, the high byte comes second
+        let channel = |i: usize| pixel[i * bytes_per_channel + bytes_per_channel - 1];
+        let rgba = match channels {
+            1 => [channel(0), channel(0), channel(0), 255],
+            2 => [channel(0), channel(0), channel(0), channel(1)],
+            3 => [channel(0), channel(1), channel(2), 255],
+            _ => [channel(0), channel(1), channel(2), channel(3)],
+        };
+        pixels.extend_from_slice(&rgba);
+        if bgr {
+            let len = pixels.len();
+            pixels.swap(len - 4, len - 2);
+        }
+    }
+    GltfImage { pixels, width: image.width, height: image.height }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_engine::mesh::MeshData;
+    use crate::game_engine::normals::NormalOptions;
+
+    // binary gltf with a json chunk and a buffer chunk
+    fn glb(json: &str, buffer: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut buffer = buffer.to_vec();
+        while !buffer.len().is_multiple_of(4) {
+            buffer.push(0);
+        }
+        let mut result = Vec::new();
+        result.extend_from_slice(b"glTF");
+        result.extend_from_slice(&2u32.to_le_bytes());
+        result.extend_from_slice(&(12 + 8 + json.len() as u32 + 8 + buffer.len() as u32).to_le_bytes());
+        result.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        result.extend_from_slice(b"JSON");
+        result.extend_from_slice(&json);
+        result.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+        result.extend_from_slice(b"BIN\0");
+        result.extend_from_slice(&buffer);
+        result
+    }
+
+    // a triangle without normals, used by a child node of a translated parent
+    fn triangle_glb() -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2].iter() {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [
+                { "name": "board", "translation": [0, 2, 0], "children": [1] },
+                { "mesh": 0, "scale": [2, 2, 2] }
+            ],
+            "meshes": [{
+                "name": "pawn",
+                "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+            }],
+            "materials": [{ "name": "white", "pbrMetallicRoughness": { "baseColorFactor": [1, 0.5, 0.5, 1] } }],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+            ],
+            "buffers": [{ "byteLength": 44 }]
+        }"#;
+        glb(json, &buffer)
+    }
+
+    #[test]
+    fn imports_meshes_materials_and_nodes() {
+        let (document, buffers, images) = gltf::import_slice(triangle_glb()).unwrap();
+        let model = convert(&document, &buffers, images).unwrap();
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.name, "pawn");
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.geometry[0].material_name.as_deref(), Some("white"));
+        match mesh.geometry[0].shapes[0].primitive {
+            obj::Primitive::Triangle(a, b, c) => assert_eq!((a, b, c), ((0, None, None), (1, None, None), (2, None, None))),
+            _ => panic!("not a triangle"),
+        }
+        assert_eq!(model.materials[0].base_color, [1.0, 0.5, 0.5, 1.0]);
+        let data = MeshData::from_object(mesh, &[], &NormalOptions::default()).unwrap();
+        assert_eq!((data.triangle_count(), data.material_count), (1, 1));
+
+        let board = &model.nodes[0];
+        assert_eq!(board.name, "board");
+        assert_eq!(board.transform.get_position(), Vector3::new(0.0, 2.0, 0.0));
+        // unnamed nodes are named after their mesh
+        let child = &board.children[0];
+        assert_eq!(child.name, "pawn");
+        assert_eq!(child.mesh, Some(0));
+        assert_eq!(child.transform.get_scale(), Vector3::new(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn strips_and_fans_become_lists() {
+        let corners = |p: &obj::Primitive| match *p {
+            obj::Primitive::Triangle(a, b, c) => vec!(a.0, b.0, c.0),
+            obj::Primitive::Line(a, b) => vec!(a.0, b.0),
+            obj::Primitive::Point(a) => vec!(a.0),
+        };
+        let strip = primitives(Mode::TriangleStrip, &[0, 1, 2, 3]).iter().map(corners).collect::<Vec<_>>();
+        assert_eq!(strip, vec!(vec!(0, 1, 2), vec!(2, 1, 3)));
+        let fan = primitives(Mode::TriangleFan, &[0, 1, 2, 3]).iter().map(corners).collect::<Vec<_>>();
+        assert_eq!(fan, vec!(vec!(0, 1, 2), vec!(0, 2, 3)));
+        let lines = primitives(Mode::LineLoop, &[0, 1, 2]).iter().map(corners).collect::<Vec<_>>();
+        assert_eq!(lines, vec!(vec!(0, 1), vec!(1, 2), vec!(2, 0)));
+    }
+}
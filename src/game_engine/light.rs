@@ -0,0 +1,14 @@
+use crate::game_engine::color::Color;
+use crate::game_engine::vector3::Vector3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Light {
+    pub position: Vector3,
+    pub color: Color,
+}
+
+impl Light {
+    pub fn new(position: Vector3, color: Color) -> Light {
+        Light { position, color }
+    }
+}
@@ -3,9 +3,48 @@ use glium::Program;
 use std::rc::Rc;
 
 use crate::game_engine::color::Color;
+use crate::game_engine::texture::TextureSlot;
 
 #[derive(Clone)]
 pub struct Material {
     pub albedo: Color,
+    // multiplied with albedo for the ambient term
+    pub ambient: Color,
+    pub specular: Color,
+    pub shininess: f32,
+    pub emissive: Color,
     pub shader: Rc<Program>,
-}
\ No newline at end of file
+    // multiplied with albedo
+    pub albedo_map: Option<TextureSlot>,
+    pub normal_map: Option<TextureSlot>,
+    // roughness in the green channel, metallic in the blue channel
+    pub roughness_metallic_map: Option<TextureSlot>,
+    pub emissive_map: Option<TextureSlot>,
+}
+
+impl Material {
+    pub fn new(albedo: Color, shader: Rc<Program>) -> Material {
+        Material {
+            albedo,
+            ambient: Color::new(1.0, 1.0, 1.0, 1.0),
+            specular: Color::new(1.0, 1.0, 1.0, 1.0),
+            shininess: 16.0,
+            emissive: Color::new(0.0, 0.0, 0.0, 1.0),
+            shader,
+            albedo_map: None,
+            normal_map: None,
+            roughness_metallic_map: None,
+            emissive_map: None,
+        }
+    }
+
+    // sampler uniform name, flag uniform name and the slot for every map
+    pub fn texture_slots(&self) -> [(&'static str, &'static str, Option<&TextureSlot>); 4] {
+        [
+            ("u_albedo_map", "u_has_albedo_map", self.albedo_map.as_ref()),
+            ("u_normal_map", "u_has_normal_map", self.normal_map.as_ref()),
+            ("u_roughness_metallic_map", "u_has_roughness_metallic_map", self.roughness_metallic_map.as_ref()),
+            ("u_emissive_map", "u_has_emissive_map", self.emissive_map.as_ref()),
+        ]
+    }
+}
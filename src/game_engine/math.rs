@@ -1,6 +1,13 @@
 use crate::game_engine::vector3::Vector3;
+use crate::game_engine::matrix4::Matrix4;
 
-pub fn view_matrix(position: Vector3, direction: Vector3, up: Vector3) -> [[f32; 4]; 4] {
+pub const EPSILON: f32 = 1.0e-5;
+
+pub fn is_equal_approx(a: f32, b: f32) -> bool {
+    (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.0)
+}
+
+pub fn view_matrix(position: Vector3, direction: Vector3, up: Vector3) -> Matrix4 {
     let front = direction.normalized();
     let right = up.cross(front).normalized();
     let up = front.cross(right);
@@ -10,22 +17,22 @@ pub fn view_matrix(position: Vector3, direction: Vector3, up: Vector3) -> [[f32;
         (-position).dot(up),
         (-position).dot(front));
 
-    [
+    Matrix4::from_columns([
         [right.x, up.x, front.x, 0.0],
         [right.y, up.y, front.y, 0.0],
         [right.z, up.z, front.z, 0.0],
         [position.x, position.y, position.z, 1.0],
-    ]
+    ])
 }
 
-pub fn perspective_matrix(frame_size: (u32, u32), fov: f32, zfar: f32, znear: f32) -> [[f32; 4]; 4] {
+pub fn perspective_matrix(frame_size: (u32, u32), fov: f32, zfar: f32, znear: f32) -> Matrix4 {
     let aspect_ratio = frame_size.1 as f32 / frame_size.0 as f32;
     let f = 1.0 / (fov / 2.0).tan();
 
-    [
+    Matrix4::from_columns([
         [f * aspect_ratio, 0.0, 0.0, 0.0],
         [0.0, f, 0.0, 0.0],
         [0.0, 0.0, (zfar+znear)/(zfar-znear), 1.0],
         [0.0, 0.0, -(2.0*zfar*znear)/(zfar-znear), 0.0],
-    ]
+    ])
 }
\ No newline at end of file
@@ -0,0 +1,167 @@
+use std::ops;
+
+use glium::uniforms::{AsUniformValue, UniformValue};
+
+use crate::game_engine::basis::Basis;
+use crate::game_engine::vector3::Vector3;
+
+// column-major, same layout as glium expects
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix3 {
+    columns: [[f32; 3]; 3],
+}
+
+impl Matrix3 {
+    pub fn from_columns(columns: [[f32; 3]; 3]) -> Matrix3 {
+        Matrix3 { columns }
+    }
+
+    pub fn identity() -> Matrix3 {
+        Matrix3::from_columns([
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn from_basis(basis: &Basis) -> Matrix3 {
+        let x = basis.get_axis(0);
+        let y = basis.get_axis(1);
+        let z = basis.get_axis(2);
+        Matrix3::from_columns([x.as_array(), y.as_array(), z.as_array()])
+    }
+
+    pub fn to_basis(self) -> Basis {
+        let c = self.columns;
+        Basis::from_axes(
+            Vector3::new(c[0][0], c[0][1], c[0][2]),
+            Vector3::new(c[1][0], c[1][1], c[1][2]),
+            Vector3::new(c[2][0], c[2][1], c[2][2]),
+        )
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f32 {
+        self.columns[column][row]
+    }
+
+    pub fn transposed(&self) -> Matrix3 {
+        let mut result = *self;
+        for c in 0..3 {
+            for r in 0..3 {
+                result.columns[c][r] = self.columns[r][c];
+            }
+        }
+        result
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let c = &self.columns;
+        c[0][0] * (c[1][1] * c[2][2] - c[2][1] * c[1][2])
+            - c[1][0] * (c[0][1] * c[2][2] - c[2][1] * c[0][2])
+            + c[2][0] * (c[0][1] * c[1][2] - c[1][1] * c[0][2])
+    }
+
+    // None if the matrix is singular. tiny but nonzero determinants are fine, small uniform
+    // scales have them
+    pub fn inverse(&self) -> Option<Matrix3> {
+        let inv_det = 1.0 / self.determinant();
+        if !inv_det.is_finite() {
+            return None;
+        }
+        let c = &self.columns;
+        Some(Matrix3::from_columns([
+            [
+                (c[1][1] * c[2][2] - c[2][1] * c[1][2]) * inv_det,
+                (c[2][1] * c[0][2] - c[0][1] * c[2][2]) * inv_det,
+                (c[0][1] * c[1][2] - c[1][1] * c[0][2]) * inv_det,
+            ],
+            [
+                (c[2][0] * c[1][2] - c[1][0] * c[2][2]) * inv_det,
+                (c[0][0] * c[2][2] - c[2][0] * c[0][2]) * inv_det,
+                (c[1][0] * c[0][2] - c[0][0] * c[1][2]) * inv_det,
+            ],
+            [
+                (c[1][0] * c[2][1] - c[2][0] * c[1][1]) * inv_det,
+                (c[2][0] * c[0][1] - c[0][0] * c[2][1]) * inv_det,
+                (c[0][0] * c[1][1] - c[1][0] * c[0][1]) * inv_det,
+            ],
+        ]))
+    }
+
+    pub fn as_array(self) -> [[f32; 3]; 3] {
+        self.columns
+    }
+}
+
+impl AsUniformValue for Matrix3 {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        UniformValue::Mat3(self.columns)
+    }
+}
+
+
+impl ops::Mul<Matrix3> for Matrix3 {
+    type Output = Matrix3;
+    fn mul(self, rhs: Matrix3) -> Self::Output {
+        let mut result = [[0.0; 3]; 3];
+        for (c, column) in result.iter_mut().enumerate() {
+            for (r, value) in column.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.columns[k][r] * rhs.columns[c][k]).sum();
+            }
+        }
+        Matrix3::from_columns(result)
+    }
+} // Matrix3 * Matrix3
+
+impl ops::Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        let c = &self.columns;
+        Vector3::new(
+            c[0][0] * rhs.x + c[1][0] * rhs.y + c[2][0] * rhs.z,
+            c[0][1] * rhs.x + c[1][1] * rhs.y + c[2][1] * rhs.z,
+            c[0][2] * rhs.x + c[1][2] * rhs.y + c[2][2] * rhs.z,
+        )
+    }
+} // Matrix3 * Vector3
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(matrix: Matrix3) {
+        let identity = Matrix3::identity();
+        for c in 0..3 {
+            for r in 0..3 {
+                assert!((matrix.get(r, c) - identity.get(r, c)).abs() < 1.0e-5, "{:?}", matrix);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_determinant_and_transpose() {
+        let mut basis = Basis::from_euler(Vector3::new(0.3, 1.2, -0.7));
+        basis.scale(Vector3::new(2.0, 3.0, 0.5));
+        let matrix = Matrix3::from_basis(&basis);
+        assert!((matrix.determinant() - 3.0).abs() < 1.0e-4);
+        assert_identity(matrix * matrix.inverse().unwrap());
+        assert_identity(matrix.inverse().unwrap() * matrix);
+        for c in 0..3 {
+            for r in 0..3 {
+                assert_eq!(matrix.transposed().get(r, c), matrix.get(c, r));
+            }
+        }
+    }
+
+    #[test]
+    fn only_singular_matrices_have_no_inverse() {
+        let diagonal = |x: f32, y: f32, z: f32| Matrix3::from_columns([[x, 0.0, 0.0], [0.0, y, 0.0], [0.0, 0.0, z]]);
+        // the determinant is about 6e-8, below f32::EPSILON
+        let small = diagonal(0.004, 0.004, 0.004);
+        assert!(small.determinant() < f32::EPSILON);
+        assert_identity(small * small.inverse().unwrap());
+        assert!(diagonal(1.0, 0.0, 1.0).inverse().is_none());
+        assert!(diagonal(f32::NAN, 1.0, 1.0).inverse().is_none());
+    }
+}
@@ -0,0 +1,266 @@
+use std::ops;
+
+use glium::uniforms::{AsUniformValue, UniformValue};
+
+use crate::game_engine::basis::Basis;
+use crate::game_engine::matrix3::Matrix3;
+use crate::game_engine::quaternion::Quaternion;
+use crate::game_engine::vector3::Vector3;
+use crate::game_engine::vector4::Vector4;
+
+// column-major, same layout as glium expects
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix4 {
+    columns: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn from_columns(columns: [[f32; 4]; 4]) -> Matrix4 {
+        Matrix4 { columns }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::from_columns([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn from_translation(translation: Vector3) -> Matrix4 {
+        let mut result = Matrix4::identity();
+        result.columns[3] = [translation.x, translation.y, translation.z, 1.0];
+        result
+    }
+
+    pub fn from_scale(scale: Vector3) -> Matrix4 {
+        Matrix4::from_basis(&Basis::from_rows(
+            Vector3::new(scale.x, 0.0, 0.0),
+            Vector3::new(0.0, scale.y, 0.0),
+            Vector3::new(0.0, 0.0, scale.z),
+        ))
+    }
+
+    pub fn from_rotation(rotation: Quaternion) -> Matrix4 {
+        Matrix4::from_basis(&rotation.normalized().to_basis())
+    }
+
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Matrix4 {
+        Matrix4::from_basis(&Basis::from_axis_angle(axis.normalized(), angle))
+    }
+
+    pub fn from_basis(basis: &Basis) -> Matrix4 {
+        Matrix4::from_basis_origin(basis, Vector3::fill(0.0))
+    }
+
+    pub fn from_basis_origin(basis: &Basis, origin: Vector3) -> Matrix4 {
+        let x = basis.get_axis(0);
+        let y = basis.get_axis(1);
+        let z = basis.get_axis(2);
+        Matrix4::from_columns([
+            [x.x, x.y, x.z, 0.0],
+            [y.x, y.y, y.z, 0.0],
+            [z.x, z.y, z.z, 0.0],
+            [origin.x, origin.y, origin.z, 1.0],
+        ])
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f32 {
+        self.columns[column][row]
+    }
+
+    pub fn get_translation(&self) -> Vector3 {
+        Vector3::new(self.columns[3][0], self.columns[3][1], self.columns[3][2])
+    }
+
+    // upper-left 3x3 part
+    pub fn to_matrix3(self) -> Matrix3 {
+        let c = self.columns;
+        Matrix3::from_columns([
+            [c[0][0], c[0][1], c[0][2]],
+            [c[1][0], c[1][1], c[1][2]],
+            [c[2][0], c[2][1], c[2][2]],
+        ])
+    }
+
+    pub fn transposed(&self) -> Matrix4 {
+        let mut result = *self;
+        for c in 0..4 {
+            for r in 0..4 {
+                result.columns[c][r] = self.columns[r][c];
+            }
+        }
+        result
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.cofactors().1
+    }
+
+    // None if the matrix is singular. tiny but nonzero determinants are fine, small uniform
+    // scales have them
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let (inv, det) = self.cofactors();
+        let inv_det = 1.0 / det;
+        if !inv_det.is_finite() {
+            return None;
+        }
+        let mut result = [[0.0; 4]; 4];
+        for (i, value) in inv.iter().enumerate() {
+            result[i / 4][i % 4] = value * inv_det;
+        }
+        Some(Matrix4::from_columns(result))
+    }
+
+    // transposed cofactor matrix (flattened) and the determinant
+    fn cofactors(&self) -> ([f32; 16], f32) {
+        let mut m = [0.0; 16];
+        for (i, value) in m.iter_mut().enumerate() {
+            *value = self.columns[i / 4][i % 4];
+        }
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14] + m[13] * m[6] * m[11] - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14] - m[12] * m[6] * m[11] + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13] + m[12] * m[5] * m[11] - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13] - m[12] * m[5] * m[10] + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14] - m[13] * m[2] * m[11] + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14] + m[12] * m[2] * m[11] - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13] - m[12] * m[1] * m[11] + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13] + m[12] * m[1] * m[10] - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14] + m[13] * m[2] * m[7] - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14] - m[12] * m[2] * m[7] + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13] + m[12] * m[1] * m[7] - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13] - m[12] * m[1] * m[6] + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10] - m[9] * m[2] * m[7] + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10] + m[8] * m[2] * m[7] - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9] - m[8] * m[1] * m[7] + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9] + m[8] * m[1] * m[6] - m[8] * m[2] * m[5];
+
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        (inv, det)
+    }
+
+    // applies translation and the perspective divide
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        let c = &self.columns;
+        let w = c[0][3] * point.x + c[1][3] * point.y + c[2][3] * point.z + c[3][3];
+        self.transform_vector(point) / w + self.get_translation() / w
+    }
+
+    // ignores translation
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        let c = &self.columns;
+        Vector3::new(
+            c[0][0] * vector.x + c[1][0] * vector.y + c[2][0] * vector.z,
+            c[0][1] * vector.x + c[1][1] * vector.y + c[2][1] * vector.z,
+            c[0][2] * vector.x + c[1][2] * vector.y + c[2][2] * vector.z,
+        )
+    }
+
+    pub fn as_array(self) -> [[f32; 4]; 4] {
+        self.columns
+    }
+}
+
+impl AsUniformValue for Matrix4 {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        UniformValue::Mat4(self.columns)
+    }
+}
+
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut result = [[0.0; 4]; 4];
+        for (c, column) in result.iter_mut().enumerate() {
+            for (r, value) in column.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.columns[k][r] * rhs.columns[c][k]).sum();
+            }
+        }
+        Matrix4::from_columns(result)
+    }
+} // Matrix4 * Matrix4
+
+impl ops::MulAssign<Matrix4> for Matrix4 {
+    fn mul_assign(&mut self, rhs: Matrix4) {
+        *self = *self * rhs;
+    }
+} // Matrix4 *= Matrix4
+
+impl ops::Mul<Vector4> for Matrix4 {
+    type Output = Vector4;
+    fn mul(self, rhs: Vector4) -> Self::Output {
+        let mut result = Vector4::default();
+        for (c, column) in self.columns.iter().enumerate() {
+            result += Vector4::from(*column) * rhs[c];
+        }
+        result
+    }
+} // Matrix4 * Vector4
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(matrix: Matrix4) {
+        let identity = Matrix4::identity();
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!((matrix.get(r, c) - identity.get(r, c)).abs() < 1.0e-5, "{:?}", matrix);
+            }
+        }
+    }
+
+    #[test]
+    fn translation_rotation_scale_compose() {
+        let matrix = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0))
+            * Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2)
+            * Matrix4::from_scale(Vector3::fill(2.0));
+        let point = matrix.transform_point(Vector3::new(1.0, 0.0, 0.0));
+        assert!((point - Vector3::new(1.0, 4.0, 3.0)).length() < 1.0e-5);
+        let vector = matrix.transform_vector(Vector3::new(1.0, 0.0, 0.0));
+        assert!((vector - Vector3::new(0.0, 2.0, 0.0)).length() < 1.0e-5);
+        let homogeneous = matrix * Vector4::new(1.0, 0.0, 0.0, 1.0);
+        assert!(homogeneous.is_equal_approx(Vector4::from_vector3(point, 1.0)));
+    }
+
+    #[test]
+    fn inverse_and_determinant() {
+        let matrix = Matrix4::from_translation(Vector3::new(-4.0, 0.5, 2.0))
+            * Matrix4::from_rotation(Quaternion::from_euler(Vector3::new(0.3, 1.2, -0.7)))
+            * Matrix4::from_scale(Vector3::new(2.0, 3.0, 0.5));
+        assert!((matrix.determinant() - 3.0).abs() < 1.0e-4);
+        assert_identity(matrix * matrix.inverse().unwrap());
+        assert_identity(matrix.inverse().unwrap() * matrix);
+        assert!(Matrix4::from_scale(Vector3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn small_scales_are_invertible() {
+        // the determinant is about 6e-8, below f32::EPSILON
+        let matrix = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0)) * Matrix4::from_scale(Vector3::fill(0.004));
+        assert!(matrix.determinant() < f32::EPSILON);
+        assert_identity(matrix * matrix.inverse().unwrap());
+        assert!(Matrix4::from_scale(Vector3::fill(f32::NAN)).inverse().is_none());
+    }
+}
@@ -1,87 +1,370 @@
 use wavefront_obj::obj;
 
+use glium::index::{IndexBuffer, IndexBufferAny, IndexType, PrimitiveType};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::vec;
 
-use crate::game_engine::vertex_types::VertexPN;
-use crate::game_engine::material::Material;
-use crate::game_engine::transform::Transform;
+use crate::game_engine::color::Color;
+use crate::game_engine::error::EngineError;
+use crate::game_engine::normals::{self, NormalOptions};
+use crate::game_engine::tangents;
+use crate::game_engine::vector3::Vector3;
+use crate::game_engine::vertex_types::{VertexColor, VertexFormat, VertexPN, VertexPNT, VertexPNTT};
+
+// vertices, their colors and the indices of every surface
+type IndexedVertices = (Vec<VertexPNT>, Vec<VertexColor>, Vec<Vec<u32>>);
 
-pub struct Mesh {
-    pub transform: Transform,
-    pub vertex_buffer: glium::VertexBuffer<VertexPN>,
-    pub index_buffers: Vec<glium::IndexBuffer<u16>>,  
-    pub draw_type: glium::index::PrimitiveType,
-    pub materials: Vec<Material>,
+// gpu buffers only, placement and materials come from the Object3D that uses the mesh
+pub struct GpuMesh {
+    // layout depends on the attributes the source file provides, see vertex_format
+    pub vertex_buffer: glium::vertex::VertexBufferAny,
+    pub vertex_format: VertexFormat,
+    // drawn together with vertex_buffer when the source has vertex colors
+    pub color_buffer: Option<glium::vertex::VertexBufferAny>,
+    pub surfaces: Vec<Surface>,
+    // number of material slots, one per obj geometry group
+    pub material_count: usize,
+    pub bounds: BoundingBox,
 }
 
-impl Mesh {
-    pub fn new(object: &obj::Object, display: &glium::Display) -> Mesh {
-        let raw_positions = &object.vertices;
-        let raw_normals = &object.normals;
-
-        let mut draw_type = glium::index::PrimitiveType::TrianglesList;
-
-        let mut indices_vec = Vec::new();
-        for shapes in &object.geometry {
-            let raw_indices = &shapes.shapes;
-            let mut raw_indices = raw_indices.iter().map(|i|
-                match i.primitive {
-                    obj::Primitive::Triangle(v1, v2, v3) => {
-                        draw_type = glium::index::PrimitiveType::TrianglesList;
-                        vec!(v1, v2, v3)
-                    },
-                    obj::Primitive::Line(v1, v2) => {
-                        draw_type = glium::index::PrimitiveType::LinesList;
-                        vec!(v1, v2)
-                    },
-                    obj::Primitive::Point(v1) => {
-                        draw_type = glium::index::PrimitiveType::Points;
-                        vec!(v1)
-                    }
-                }
-            ).collect::<Vec<Vec<obj::VTNIndex>>>().concat();
-            indices_vec.push(raw_indices);
+// indices of one primitive type drawn with one material
+pub struct Surface {
+    // u16 or u32 indices depending on the vertex count, see MeshData::index_type_for
+    pub indices: IndexBufferAny,
+    pub primitive: PrimitiveType,
+    // index into the materials of the Object3D
+    pub material: usize,
+}
+
+// cpu side of a mesh, importers build it without a display and GpuMesh::upload uploads it
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshData {
+    pub vertices: Vertices,
+    // empty or one per vertex
+    pub colors: Vec<VertexColor>,
+    pub surfaces: Vec<SurfaceData>,
+    pub material_count: usize,
+    pub bounds: BoundingBox,
+}
+
+// vertices in the layout they are uploaded with
+#[derive(Clone, Debug, PartialEq)]
+pub enum Vertices {
+    Pn(Vec<VertexPN>),
+    Pnt(Vec<VertexPNT>),
+    Pntt(Vec<VertexPNTT>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceData {
+    pub primitive: PrimitiveType,
+    pub material: usize,
+    pub indices: Vec<u32>,
+}
+
+// axis aligned, in mesh space
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl BoundingBox {
+    // zero sized at the origin if there are no points
+    pub fn from_points(points: impl IntoIterator<Item = Vector3>) -> BoundingBox {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(first) => first,
+            None => return BoundingBox::default(),
+        };
+        points.fold(BoundingBox { min: first, max: first }, |b, p| BoundingBox {
+            min: Vector3::new(b.min.x.min(p.x), b.min.y.min(p.y), b.min.z.min(p.z)),
+            max: Vector3::new(b.max.x.max(p.x), b.max.y.max(p.y), b.max.z.max(p.z)),
+        })
+    }
+}
+
+impl Vertices {
+    pub fn format(&self) -> VertexFormat {
+        match self {
+            Vertices::Pn(_) => VertexFormat::PositionNormal,
+            Vertices::Pnt(_) => VertexFormat::PositionNormalUv,
+            Vertices::Pntt(_) => VertexFormat::PositionNormalUvTangent,
         }
-        
-        let result = Mesh::correct_input(&raw_positions, &raw_normals, &indices_vec);
+    }
 
-        let vertex_buffer = glium::VertexBuffer::new(display, &result.0).unwrap();
-        let mut index_buffers = Vec::new();
-        for indices in result.1 {
-            index_buffers.push(glium::IndexBuffer::new(display, draw_type, &indices).unwrap());
+    pub fn len(&self) -> usize {
+        match self {
+            Vertices::Pn(vertices) => vertices.len(),
+            Vertices::Pnt(vertices) => vertices.len(),
+            Vertices::Pntt(vertices) => vertices.len(),
         }
+    }
 
-        Mesh {
-            transform: Transform::new(),
-            vertex_buffer,
-            index_buffers,
-            draw_type,
-            materials: Vec::new(),
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn positions(&self) -> Vec<Vector3> {
+        let position = |p: (f32, f32, f32)| Vector3::new(p.0, p.1, p.2);
+        match self {
+            Vertices::Pn(vertices) => vertices.iter().map(|v| position(v.position)).collect(),
+            Vertices::Pnt(vertices) => vertices.iter().map(|v| position(v.position)).collect(),
+            Vertices::Pntt(vertices) => vertices.iter().map(|v| position(v.position)).collect(),
+        }
+    }
+}
+
+impl MeshData {
+    // missing normals are generated, all of them if the options force it.
+    // colors are indexed like object.vertices, an empty slice means the mesh has none
+    pub fn from_object(object: &obj::Object, colors: &[Color], options: &NormalOptions) -> Result<MeshData, EngineError> {
+        let generated;
+        let object = if normals::needs_normals(object, options) {
+            generated = normals::generate_normals(object, options);
+            &generated
+        } else {
+            object
+        };
+
+        let surfaces = MeshData::split_surfaces(object);
+        let indices_vec = surfaces.iter().map(|s| s.2.clone()).collect::<Vec<_>>();
+
+        let (vertices, mut vertex_colors, mut indices) =
+            MeshData::correct_input(&object.name, &object.vertices, &object.normals, &object.tex_vertices, colors, &indices_vec)?;
+
+        // uvs are only kept if at least one face uses them, tangents need uvs
+        let has_uvs = indices_vec.iter().flatten().any(|i| i.1.is_some());
+        let tangent_input = surfaces.iter().map(|s| s.0).zip(indices.iter().cloned()).collect::<Vec<_>>();
+        let with_tangents = if has_uvs { tangents::generate_tangents(&vertices, &tangent_input) } else { None };
+        let vertices = match with_tangents {
+            Some(tangent_mesh) => {
+                indices = tangent_mesh.indices;
+                if !vertex_colors.is_empty() {
+                    vertex_colors = tangent_mesh.sources.iter().map(|&i| vertex_colors[i as usize]).collect();
+                }
+                Vertices::Pntt(tangent_mesh.vertices)
+            },
+            None if has_uvs => Vertices::Pnt(vertices),
+            None => Vertices::Pn(vertices.iter().map(|&v| VertexPN::from(v)).collect()),
+        };
+        // rejected here instead of at upload so headless imports fail the same way
+        MeshData::index_type_for(vertices.len())?;
+
+        Ok(MeshData {
+            bounds: BoundingBox::from_points(vertices.positions()),
+            vertices,
+            colors: vertex_colors,
+            surfaces: surfaces.into_iter().zip(indices)
+                .map(|((primitive, material, _), indices)| SurfaceData { primitive, material, indices })
+                .collect(),
+            material_count: object.geometry.len(),
+        })
+    }
+
+    // smallest index type that can address every vertex, 0xFFFF is left free for primitive restart
+    pub fn index_type_for(vertex_count: usize) -> Result<IndexType, EngineError> {
+        if vertex_count <= u16::MAX as usize {
+            Ok(IndexType::U16)
+        } else if vertex_count <= u32::MAX as usize {
+            Ok(IndexType::U32)
+        } else {
+            Err(EngineError::TooManyVertices(vertex_count))
         }
     }
 
-    // returns vertices and indices
-    fn correct_input(raw_positions: &[obj::Vertex], raw_normals: &[obj::Vertex], raw_indices: &Vec<Vec<obj::VTNIndex>>)
-        -> (Vec<VertexPN>, Vec<Vec<u16>>) {
+    pub fn index_type(&self) -> Result<IndexType, EngineError> {
+        MeshData::index_type_for(self.vertices.len())
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.surfaces.iter()
+            .filter(|s| s.primitive == PrimitiveType::TrianglesList)
+            .map(|s| s.indices.len() / 3)
+            .sum()
+    }
+
+    // splits every geometry group by primitive type, in order of first appearance,
+    // returns the primitive type, the geometry index and the indices of each surface
+    fn split_surfaces(object: &obj::Object) -> Vec<(PrimitiveType, usize, Vec<obj::VTNIndex>)> {
+        let mut surfaces = Vec::new();
+        for (geometry_i, geometry) in object.geometry.iter().enumerate() {
+            let first_surface = surfaces.len();
+            for shape in &geometry.shapes {
+                let (primitive, indices) = match shape.primitive {
+                    obj::Primitive::Triangle(v1, v2, v3) => (PrimitiveType::TrianglesList, vec!(v1, v2, v3)),
+                    obj::Primitive::Line(v1, v2) => (PrimitiveType::LinesList, vec!(v1, v2)),
+                    obj::Primitive::Point(v1) => (PrimitiveType::Points, vec!(v1)),
+                };
+                let existing = surfaces[first_surface..].iter()
+                    .position(|(p, _, _): &(PrimitiveType, usize, Vec<obj::VTNIndex>)| *p == primitive);
+                match existing {
+                    Some(i) => surfaces[first_surface + i].2.extend(indices),
+                    None => surfaces.push((primitive, geometry_i, indices)),
+                }
+            }
+        }
+        surfaces
+    }
+
+    // returns vertices, their colors and indices, fails if a face has no normal
+    // faces without texture coordinates get (0, 0). colors are empty if raw_colors is
+    fn correct_input(name: &str, raw_positions: &[obj::Vertex], raw_normals: &[obj::Vertex], raw_tex_vertices: &[obj::TVertex],
+        raw_colors: &[Color], raw_indices: &Vec<Vec<obj::VTNIndex>>) -> Result<IndexedVertices, EngineError> {
+
+        let mut vertices = Vec::<VertexPNT>::new();
+        let mut colors = Vec::<VertexColor>::new();
+        let mut indices = Vec::<Vec<u32>>::new();
+        // bit-exact vertex data to its index, so identical data from different obj indices is merged too
+        let mut vertex_lookup = HashMap::<([u32; 8], [u32; 4]), u32>::new();
 
-        let mut vertices = Vec::<VertexPN>::new();
-        let mut indices = Vec::<Vec<u16>>::new();
-        
         for ind in raw_indices {
-            let mut inner_indices = Vec::<u16>::new(); 
+            let mut inner_indices = Vec::<u32>::new();
             for i in 0..ind.len() {
                 let vertex = raw_positions[ind[i].0];
-                let normal = raw_normals[ind[i].2.unwrap()];
+                let normal = match ind[i].2 {
+                    Some(n) => raw_normals[n],
+                    None => return Err(EngineError::MissingAttribute { path: None, object: name.to_string(), attribute: "normals" }),
+                };
+                let uv = ind[i].1.map(|t| raw_tex_vertices[t])
+                    .unwrap_or(obj::TVertex { u: 0.0, v: 0.0, w: 0.0 });
+                let candidate = VertexPNT {
+                    position: (vertex.x as f32, vertex.y as f32, vertex.z as f32),
+                    normal: (normal.x as f32, normal.y as f32, normal.z as f32),
+                    uv: (uv.u as f32, uv.v as f32),
+                };
+                let color = raw_colors.get(ind[i].0)
+                    .map(|c| VertexColor { color: (c.r, c.g, c.b, c.a) })
+                    .unwrap_or(VertexColor { color: (1.0, 1.0, 1.0, 1.0) });
+                let key = (candidate.key(), if raw_colors.is_empty() { [0; 4] } else { color.key() });
 
-                let found_index = Mesh::find_same_vertex(vertex, normal, &vertices);
-                match found_index {
-                    Some(v) => {
-                        inner_indices.push(v as u16);
-                    },
+                let index = match vertex_lookup.get(&key) {
+                    Some(&index) => index,
                     None => {
-                        vertices.push(VertexPN { position: (vertex.x as f32, vertex.y as f32, vertex.z as f32),
-                            normal: (normal.x as f32, normal.y as f32, normal.z as f32) });
-                        inner_indices.push((vertices.len() - 1) as u16);
+                        let index = u32::try_from(vertices.len())
+                            .map_err(|_| EngineError::TooManyVertices(vertices.len() + 1))?;
+                        vertices.push(candidate);
+                        if !raw_colors.is_empty() {
+                            colors.push(color);
+                        }
+                        vertex_lookup.insert(key, index);
+                        index
+                    }
+                };
+                inner_indices.push(index);
+            }
+            indices.push(inner_indices);
+        }
+        Ok((vertices, colors, indices))
+    }
+}
+
+impl GpuMesh {
+    pub fn upload(data: &MeshData, display: &glium::Display) -> Result<GpuMesh, EngineError> {
+        let vertex_buffer = match &data.vertices {
+            Vertices::Pn(vertices) => glium::VertexBuffer::new(display, vertices)?.into(),
+            Vertices::Pnt(vertices) => glium::VertexBuffer::new(display, vertices)?.into(),
+            Vertices::Pntt(vertices) => glium::VertexBuffer::new(display, vertices)?.into(),
+        };
+        let color_buffer = if data.colors.is_empty() {
+            None
+        } else {
+            Some(glium::VertexBuffer::new(display, &data.colors)?.into())
+        };
+        let index_type = data.index_type()?;
+        let mut surfaces = Vec::new();
+        for surface in &data.surfaces {
+            surfaces.push(Surface {
+                indices: GpuMesh::index_buffer(display, surface.primitive, index_type, &surface.indices)?,
+                primitive: surface.primitive,
+                material: surface.material,
+            });
+        }
+
+        Ok(GpuMesh {
+            vertex_buffer,
+            vertex_format: data.vertices.format(),
+            color_buffer,
+            surfaces,
+            material_count: data.material_count,
+            bounds: data.bounds,
+        })
+    }
+
+    fn index_buffer(display: &glium::Display, draw_type: PrimitiveType, index_type: IndexType, indices: &[u32])
+        -> Result<IndexBufferAny, EngineError> {
+        Ok(match index_type {
+            IndexType::U8 | IndexType::U16 => {
+                let indices = indices.iter().map(|&i| i as u16).collect::<Vec<u16>>();
+                IndexBuffer::new(display, draw_type, &indices)?.into()
+            },
+            IndexType::U32 => IndexBuffer::new(display, draw_type, indices)?.into(),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_normals_are_reported() {
+        let positions = vec!(obj::Vertex { x: 0.0, y: 0.0, z: 0.0 });
+        let normals = vec!(obj::Vertex { x: 0.0, y: 1.0, z: 0.0 });
+        let with_normals = vec!(vec!((0, None, Some(0)), (0, None, Some(0))));
+        let (vertices, _, indices) = MeshData::correct_input("test", &positions, &normals, &[], &[], &with_normals).unwrap();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(indices, vec!(vec!(0, 0)));
+
+        let without_normals = vec!(vec!((0, None, Some(0)), (0, None, None)));
+        match MeshData::correct_input("test", &positions, &normals, &[], &[], &without_normals) {
+            Err(EngineError::MissingAttribute { attribute, .. }) => assert_eq!(attribute, "normals"),
+            _ => panic!("missing normals not reported"),
+        }
+    }
+
+    #[test]
+    fn texture_coordinates_split_vertices() {
+        let positions = vec!(obj::Vertex { x: 0.0, y: 0.0, z: 0.0 });
+        let normals = vec!(obj::Vertex { x: 0.0, y: 1.0, z: 0.0 });
+        let tex_vertices = vec!(obj::TVertex { u: 0.0, v: 0.0, w: 0.0 }, obj::TVertex { u: 0.5, v: 1.0, w: 0.0 });
+        let raw_indices = vec!(vec!((0, Some(0), Some(0)), (0, Some(1), Some(0)), (0, Some(1), Some(0))));
+        let (vertices, _, indices) = MeshData::correct_input("test", &positions, &normals, &tex_vertices, &[], &raw_indices).unwrap();
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(vertices[1].uv, (0.5, 1.0));
+        assert_eq!(indices, vec!(vec!(0, 1, 1)));
+    }
+
+    // the linear search correct_input used before hashing, kept to check the output did not change
+    fn correct_input_linear(object: &obj::Object) -> (Vec<VertexPNT>, Vec<Vec<u32>>) {
+        let mut vertices = Vec::<VertexPNT>::new();
+        let mut indices = Vec::new();
+        for geometry in &object.geometry {
+            let mut inner_indices = Vec::new();
+            for shape in &geometry.shapes {
+                let shape_indices = match shape.primitive {
+                    obj::Primitive::Triangle(v1, v2, v3) => vec!(v1, v2, v3),
+                    obj::Primitive::Line(v1, v2) => vec!(v1, v2),
+                    obj::Primitive::Point(v1) => vec!(v1),
+                };
+                for (p, t, n) in shape_indices {
+                    let position = object.vertices[p];
+                    let normal = object.normals[n.unwrap()];
+                    let uv = t.map(|t| object.tex_vertices[t]).unwrap_or(obj::TVertex { u: 0.0, v: 0.0, w: 0.0 });
+                    let vertex = VertexPNT {
+                        position: (position.x as f32, position.y as f32, position.z as f32),
+                        normal: (normal.x as f32, normal.y as f32, normal.z as f32),
+                        uv: (uv.u as f32, uv.v as f32),
+                    };
+                    match vertices.iter().position(|v| *v == vertex) {
+                        Some(i) => inner_indices.push(i as u32),
+                        None => {
+                            vertices.push(vertex);
+                            inner_indices.push((vertices.len() - 1) as u32);
+                        }
                     }
                 }
             }
@@ -90,13 +373,78 @@ impl Mesh {
         (vertices, indices)
     }
 
-    // return index if vertex found
-    fn find_same_vertex(vertex: obj::Vertex, normal: obj::Vertex, vertices: &[VertexPN]) -> Option<u16> {
-        for i in 0..vertices.len() {
-            if vertices[i] == (vertex, normal) {
-                return Some(i as u16);
-            }
+    #[test]
+    fn hashed_deduplication_matches_linear_search() {
+        for name in ["bishop", "board", "king", "knight", "pawn", "queen", "rook"].iter() {
+            let source = std::fs::read_to_string(format!("assets/models/{}.obj", name)).unwrap();
+            let object = &obj::parse(source).unwrap().objects[0];
+            let raw_indices = MeshData::split_surfaces(object).into_iter().map(|s| s.2).collect::<Vec<_>>();
+
+            let (vertices, _, indices) = MeshData::correct_input(&object.name, &object.vertices, &object.normals, &object.tex_vertices, &[], &raw_indices).unwrap();
+            let (expected_vertices, expected_indices) = correct_input_linear(object);
+
+            assert_eq!(vertices, expected_vertices, "{}", name);
+            assert_eq!(indices, expected_indices, "{}", name);
         }
-        None
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn vertex_colors_split_vertices() {
+        // two positions at the same place, only their colors differ
+        let positions = vec!(obj::Vertex { x: 0.0, y: 0.0, z: 0.0 }, obj::Vertex { x: 0.0, y: 0.0, z: 0.0 });
+        let normals = vec!(obj::Vertex { x: 0.0, y: 1.0, z: 0.0 });
+        let raw_indices = vec!(vec!((0, None, Some(0)), (1, None, Some(0))));
+        let (vertices, colors, _) = MeshData::correct_input("test", &positions, &normals, &[], &[], &raw_indices).unwrap();
+        assert_eq!((vertices.len(), colors.len()), (1, 0));
+
+        let red_and_blue = vec!(Color::new(1.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0));
+        let (vertices, colors, indices) = MeshData::correct_input("test", &positions, &normals, &[], &red_and_blue, &raw_indices).unwrap();
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(colors[1].color, (0.0, 0.0, 1.0, 1.0));
+        assert_eq!(indices, vec!(vec!(0, 1)));
+    }
+
+    #[test]
+    fn bundled_model_without_display() {
+        let source = std::fs::read_to_string("assets/models/pawn.obj").unwrap();
+        let object = &obj::parse(source).unwrap().objects[0];
+        let data = MeshData::from_object(object, &[], &NormalOptions::default()).unwrap();
+        assert_eq!(data.vertices.format(), VertexFormat::PositionNormal);
+        assert_eq!(data.triangle_count(), object.geometry[0].shapes.len());
+        assert_eq!((data.surfaces.len(), data.material_count), (1, 1));
+        assert!(data.colors.is_empty());
+        assert!(data.bounds.min.y < 0.01 && data.bounds.max.y > 1.5);
+    }
+
+    #[test]
+    fn index_type_depends_on_vertex_count() {
+        assert_eq!(MeshData::index_type_for(3).unwrap(), IndexType::U16);
+        assert_eq!(MeshData::index_type_for(65_535).unwrap(), IndexType::U16);
+        assert_eq!(MeshData::index_type_for(65_536).unwrap(), IndexType::U32);
+        assert_eq!(MeshData::index_type_for(1_000_000).unwrap(), IndexType::U32);
+    }
+
+    #[test]
+    fn mixed_primitives_get_separate_surfaces() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            vn 0 0 1
+            usemtl a
+            f 1//1 2//1 3//1
+            l 1//1 2//1
+            f 3//1 2//1 1//1
+            usemtl b
+            l 3//1
+        ";
+        let object = &obj::parse(source).unwrap().objects[0];
+        let surfaces = MeshData::split_surfaces(object);
+        let summary = surfaces.iter().map(|(p, m, i)| (*p, *m, i.len())).collect::<Vec<_>>();
+        assert_eq!(summary, vec!(
+            (PrimitiveType::TrianglesList, 0, 6),
+            (PrimitiveType::LinesList, 0, 2),
+            (PrimitiveType::Points, 1, 1),
+        ));
+    }
+}
@@ -0,0 +1,541 @@
+use glium::index::PrimitiveType;
+use wavefront_obj::obj;
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::game_engine::asset_manager::{join_relative, split_objects};
+use crate::game_engine::error::EngineError;
+use crate::game_engine::mesh::{BoundingBox, MeshData, SurfaceData, Vertices};
+use crate::game_engine::normals::NormalOptions;
+use crate::game_engine::ply_import;
+use crate::game_engine::stl_import;
+use crate::game_engine::vector3::Vector3;
+use crate::game_engine::vertex_types::{VertexColor, VertexPN, VertexPNT, VertexPNTT};
+
+// files are rebuilt when the version changes
+pub const VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"GEMC";
+
+// meshes of an obj, stl or ply file ready for upload
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshFile {
+    // mtl library relative to the asset root
+    pub library: Option<String>,
+    pub meshes: Vec<NamedMesh>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedMesh {
+    pub name: String,
+    pub data: MeshData,
+    // one per material slot
+    pub materials: Vec<Option<String>>,
+}
+
+// what a cache file was built from, all zero for files that are not a cache
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Header {
+    pub source: SourceStamp,
+    // hash of the import options
+    pub settings: u64,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SourceStamp {
+    pub size: u64,
+    // nanoseconds since the unix epoch
+    pub modified: u64,
+    pub hash: u64,
+}
+
+impl SourceStamp {
+    pub fn new(bytes: &[u8], metadata: &fs::Metadata) -> SourceStamp {
+        SourceStamp {
+            size: bytes.len() as u64,
+            modified: modified_nanos(metadata),
+            hash: fnv1a(bytes),
+        }
+    }
+}
+
+// meshes of the file at path, relative to root. with a cache directory the file is read
+// from cache_file if that was built from the same source and options, otherwise
+// it is imported and the cache is rewritten
+pub fn load(root: &Path, path: &str, cache: Option<&Path>, options: &NormalOptions) -> Result<MeshFile, EngineError> {
+    let source_file = root.join(path);
+    let settings = settings_hash(options);
+    // .mesh files are already in the cache format
+    let cache_file = cache.filter(|_| !path.ends_with(".mesh")).map(|cache| cache_file(cache, path));
+    if let Some(cache_file) = &cache_file {
+        if let Some(model) = read_valid(cache_file, &source_file, settings) {
+            return Ok(model);
+        }
+    }
+
+    let bytes = fs::read(&source_file).map_err(|e| EngineError::io(&source_file, e))?;
+    let model = import(&bytes, path, options).map_err(|e| e.with_path(&source_file))?;
+    if let Some(cache_file) = &cache_file {
+        if let Ok(metadata) = fs::metadata(&source_file) {
+            let header = Header { source: SourceStamp::new(&bytes, &metadata), settings };
+            // a cache that can't be written only costs load time
+            let _ = write(cache_file, &header, &model);
+        }
+    }
+    Ok(model)
+}
+
+// "<cache>/<path>.mesh". paths that could leave the cache directory, absolute ones or ones
+// with "..", are stored under the hash of the path instead
+pub fn cache_file(cache: &Path, path: &str) -> PathBuf {
+    let parts = path.split(['/', '\\']).filter(|p| !p.is_empty() && *p != ".").collect::<Vec<_>>();
+    let escapes = Path::new(path).has_root() || path.contains(':') || parts.contains(&"..");
+    if escapes || parts.is_empty() {
+        cache.join(format!("{:016x}.mesh", fnv1a(path.as_bytes())))
+    } else {
+        cache.join(format!("{}.mesh", parts.join("/")))
+    }
+}
+
+// parses an obj, stl or ply file by the extension of path, a .mesh file is read as it is
+pub fn import(bytes: &[u8], path: &str, options: &NormalOptions) -> Result<MeshFile, EngineError> {
+    let file = Path::new(path);
+    let name = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let single = |object: obj::Object, colors: &[_]| -> Result<MeshFile, EngineError> {
+        Ok(MeshFile {
+            library: None,
+            meshes: vec!(NamedMesh {
+                name: object.name.clone(),
+                data: MeshData::from_object(&object, colors, options)?,
+                materials: vec!(None; object.geometry.len()),
+            }),
+        })
+    };
+    match extension.as_str() {
+        "mesh" => from_bytes(bytes).map(|(_, model)| model)
+            .map_err(|message| EngineError::ModelParse { path: None, message }),
+        "stl" => single(stl_import::parse(bytes, &name)?, &[]),
+        "ply" => {
+            let (object, colors) = ply_import::parse(bytes, &name)?;
+            single(object, &colors)
+        },
+        _ => {
+            let source = String::from_utf8_lossy(bytes).into_owned();
+            let object_set = obj::parse(source)
+                .map_err(|e| EngineError::ObjParse { path: None, line: e.line_number, message: e.message })?;
+            let mut meshes = Vec::new();
+            for object in split_objects(&object_set) {
+                meshes.push(NamedMesh {
+                    name: object.name.clone(),
+                    data: MeshData::from_object(&object, &[], options)?,
+                    materials: object.geometry.iter().map(|g| g.material_name.clone()).collect(),
+                });
+            }
+            Ok(MeshFile {
+                library: object_set.material_library.as_ref().map(|library| join_relative(path, library)),
+                meshes,
+            })
+        },
+    }
+}
+
+// the cache if it exists, has the current version and was built from the same source and options.
+// a source with a new modification time but the same contents is still valid
+fn read_valid(cache_file: &Path, source_file: &Path, settings: u64) -> Option<MeshFile> {
+    let (header, model) = from_bytes(&fs::read(cache_file).ok()?).ok()?;
+    let metadata = fs::metadata(source_file).ok()?;
+    if header.settings != settings || header.source.size != metadata.len() {
+        return None;
+    }
+    if header.source.modified == modified_nanos(&metadata) {
+        return Some(model);
+    }
+    let bytes = fs::read(source_file).ok()?;
+    if fnv1a(&bytes) != header.source.hash {
+        return None;
+    }
+    // keeps the next launch from hashing the source again
+    let header = Header { source: SourceStamp::new(&bytes, &metadata), settings };
+    let _ = write(cache_file, &header, &model);
+    Some(model)
+}
+
+pub fn write(file: &Path, header: &Header, model: &MeshFile) -> Result<(), EngineError> {
+    if let Some(directory) = file.parent() {
+        fs::create_dir_all(directory).map_err(|e| EngineError::io(directory, e))?;
+    }
+    fs::write(file, to_bytes(header, model)).map_err(|e| EngineError::io(file, e))
+}
+
+pub fn read(file: &Path) -> Result<(Header, MeshFile), EngineError> {
+    let bytes = fs::read(file).map_err(|e| EngineError::io(file, e))?;
+    from_bytes(&bytes).map_err(|message| EngineError::ModelParse { path: Some(file.to_path_buf()), message })
+}
+
+// little endian. after the magic and version: header, library and meshes. every mesh has its
+// name, vertex layout, vertex count, bounds, material names, surfaces with their index width
+// and indices, then the interleaved vertices and the colors
+pub fn to_bytes(header: &Header, model: &MeshFile) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    put_u32(&mut out, VERSION);
+    for value in [header.settings, header.source.size, header.source.modified, header.source.hash].iter() {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    put_option(&mut out, &model.library);
+    put_u32(&mut out, model.meshes.len() as u32);
+
+    for mesh in &model.meshes {
+        let data = &mesh.data;
+        put_string(&mut out, &mesh.name);
+        let layout = layout(&data.vertices, !data.colors.is_empty());
+        out.push(layout.len() as u8);
+        for (name, components) in &layout {
+            put_string(&mut out, name);
+            out.push(*components);
+        }
+        put_u32(&mut out, data.vertices.len() as u32);
+        for value in [data.bounds.min, data.bounds.max].iter().flat_map(|v| vec!(v.x, v.y, v.z)) {
+            put_f32(&mut out, value);
+        }
+        put_u32(&mut out, mesh.materials.len() as u32);
+        for material in &mesh.materials {
+            put_option(&mut out, material);
+        }
+
+        put_u32(&mut out, data.surfaces.len() as u32);
+        let wide = data.vertices.len() > u16::MAX as usize;
+        for surface in &data.surfaces {
+            out.push(match surface.primitive {
+                PrimitiveType::Points => 0,
+                PrimitiveType::LinesList => 1,
+                _ => 2,
+            });
+            put_u32(&mut out, surface.material as u32);
+            out.push(if wide { 4 } else { 2 });
+            put_u32(&mut out, surface.indices.len() as u32);
+            for &index in &surface.indices {
+                if wide {
+                    put_u32(&mut out, index);
+                } else {
+                    out.extend_from_slice(&(index as u16).to_le_bytes());
+                }
+            }
+        }
+
+        let mut floats = Vec::new();
+        match &data.vertices {
+            Vertices::Pn(vertices) => for v in vertices {
+                floats.extend_from_slice(&[v.position.0, v.position.1, v.position.2, v.normal.0, v.normal.1, v.normal.2]);
+            },
+            Vertices::Pnt(vertices) => for v in vertices {
+                floats.extend_from_slice(&[v.position.0, v.position.1, v.position.2, v.normal.0, v.normal.1, v.normal.2,
+                    v.uv.0, v.uv.1]);
+            },
+            Vertices::Pntt(vertices) => for v in vertices {
+                floats.extend_from_slice(&[v.position.0, v.position.1, v.position.2, v.normal.0, v.normal.1, v.normal.2,
+                    v.uv.0, v.uv.1, v.tangent.0, v.tangent.1, v.tangent.2, v.tangent.3]);
+            },
+        }
+        for c in &data.colors {
+            floats.extend_from_slice(&[c.color.0, c.color.1, c.color.2, c.color.3]);
+        }
+        for value in floats {
+            put_f32(&mut out, value);
+        }
+    }
+    out
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<(Header, MeshFile), String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err("not a mesh file".to_string());
+    }
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(format!("version {}, expected {}", version, VERSION));
+    }
+    let header = Header {
+        settings: reader.u64()?,
+        source: SourceStamp { size: reader.u64()?, modified: reader.u64()?, hash: reader.u64()? },
+    };
+    let library = reader.option()?;
+    let mesh_count = reader.u32()?;
+
+    let mut meshes = Vec::new();
+    for _ in 0..mesh_count {
+        let name = reader.string()?;
+        let mut layout = Vec::new();
+        for _ in 0..reader.u8()? {
+            layout.push((reader.string()?, reader.u8()?));
+        }
+        let vertex_count = reader.u32()? as usize;
+        let mut bounds = [0.0; 6];
+        for value in bounds.iter_mut() {
+            *value = reader.f32()?;
+        }
+        let material_names = (0..reader.u32()?).map(|_| reader.option()).collect::<Result<Vec<_>, String>>()?;
+
+        let mut surfaces = Vec::new();
+        for _ in 0..reader.u32()? {
+            let primitive = match reader.u8()? {
+                0 => PrimitiveType::Points,
+                1 => PrimitiveType::LinesList,
+                2 => PrimitiveType::TrianglesList,
+                other => return Err(format!("unknown primitive {}", other)),
+            };
+            let material = reader.u32()? as usize;
+            let width = reader.u8()?;
+            let count = reader.u32()? as usize;
+            let indices = match width {
+                2 => reader.take(count * 2)?.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32).collect::<Vec<_>>(),
+                4 => reader.take(count * 4)?.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+                other => return Err(format!("unknown index width {}", other)),
+            };
+            if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+                return Err(format!("index {} but {} vertices", index, vertex_count));
+            }
+            surfaces.push(SurfaceData { primitive, material, indices });
+        }
+
+        let names = layout.iter().map(|(name, components)| (name.as_str(), *components)).collect::<Vec<_>>();
+        let has_colors = names.last() == Some(&("color", 4));
+        let vertex_layout = if has_colors { &names[..names.len() - 1] } else { &names[..] };
+        let mut floats = |count: usize| -> Result<Vec<f32>, String> {
+            Ok(reader.take(count * 4)?.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+        };
+        let vertices = match vertex_layout {
+            [("position", 3), ("normal", 3)] => Vertices::Pn(floats(vertex_count * 6)?.chunks_exact(6).map(|f| VertexPN {
+                position: (f[0], f[1], f[2]),
+                normal: (f[3], f[4], f[5]),
+            }).collect()),
+            [("position", 3), ("normal", 3), ("uv", 2)] => Vertices::Pnt(floats(vertex_count * 8)?.chunks_exact(8).map(|f| VertexPNT {
+                position: (f[0], f[1], f[2]),
+                normal: (f[3], f[4], f[5]),
+                uv: (f[6], f[7]),
+            }).collect()),
+            [("position", 3), ("normal", 3), ("uv", 2), ("tangent", 4)] =>
+                Vertices::Pntt(floats(vertex_count * 12)?.chunks_exact(12).map(|f| VertexPNTT {
+                    position: (f[0], f[1], f[2]),
+                    normal: (f[3], f[4], f[5]),
+                    uv: (f[6], f[7]),
+                    tangent: (f[8], f[9], f[10], f[11]),
+                }).collect()),
+            _ => return Err(format!("unknown vertex layout {:?}", layout)),
+        };
+        let colors = if has_colors {
+            floats(vertex_count * 4)?.chunks_exact(4).map(|f| VertexColor { color: (f[0], f[1], f[2], f[3]) }).collect()
+        } else {
+            Vec::new()
+        };
+
+        meshes.push(NamedMesh {
+            name,
+            data: MeshData {
+                vertices,
+                colors,
+                surfaces,
+                material_count: material_names.len(),
+                bounds: BoundingBox {
+                    min: Vector3::new(bounds[0], bounds[1], bounds[2]),
+                    max: Vector3::new(bounds[3], bounds[4], bounds[5]),
+                },
+            },
+            materials: material_names,
+        });
+    }
+    Ok((header, MeshFile { library, meshes }))
+}
+
+// attribute names and component counts in buffer order, colors are a second buffer
+fn layout(vertices: &Vertices, has_colors: bool) -> Vec<(&'static str, u8)> {
+    let mut layout = vec!(("position", 3), ("normal", 3));
+    match vertices {
+        Vertices::Pn(_) => (),
+        Vertices::Pnt(_) => layout.push(("uv", 2)),
+        Vertices::Pntt(_) => layout.extend_from_slice(&[("uv", 2), ("tangent", 4)]),
+    }
+    if has_colors {
+        layout.push(("color", 4));
+    }
+    layout
+}
+
+fn settings_hash(options: &NormalOptions) -> u64 {
+    let mut bytes = vec!(options.smooth as u8, options.recompute as u8);
+    bytes.extend_from_slice(&options.crease_angle.to_le_bytes());
+    fnv1a(&bytes)
+}
+
+fn modified_nanos(metadata: &fs::Metadata) -> u64 {
+    metadata.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// 64 bit fnv-1a, stable across builds unlike the std hasher
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_f32(out: &mut Vec<u8>, value: f32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    put_u32(out, value.len() as u32);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn put_option(out: &mut Vec<u8>, value: &Option<String>) {
+    match value {
+        Some(value) => {
+            out.push(1);
+            put_string(out, value);
+        },
+        None => out.push(0),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self.bytes.get(self.position..self.position + count).ok_or("unexpected end of file")?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn option(&mut self) -> Result<Option<String>, String> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => self.string().map(Some),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "
+        mtllib quad.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 1
+        usemtl white
+        f 1/1 2/1 3/2
+        usemtl black
+        f 1/1 3/2 4/2
+        l 1 2
+    ";
+
+    #[test]
+    fn round_trip_keeps_meshes() {
+        let model = import(QUAD.as_bytes(), "models/quad.obj", &NormalOptions::default()).unwrap();
+        assert_eq!(model.library.as_deref(), Some("models/quad.mtl"));
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.data.vertices.format(), crate::game_engine::vertex_types::VertexFormat::PositionNormalUvTangent);
+        assert_eq!(mesh.materials, vec!(Some("white".to_string()), Some("black".to_string())));
+        assert_eq!(mesh.data.bounds.max, Vector3::new(1.0, 1.0, 0.0));
+
+        let header = Header { source: SourceStamp { size: 1, modified: 2, hash: 3 }, settings: 4 };
+        let (read_header, read_model) = from_bytes(&to_bytes(&header, &model)).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(read_model, model);
+
+        // any supported file can be stored, including vertex colors
+        let ply = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\n\
+            property uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n0 0 0 255 0 0\n";
+        let model = import(ply.as_bytes(), "scan.ply", &NormalOptions::default()).unwrap();
+        assert_eq!(from_bytes(&to_bytes(&Header::default(), &model)).unwrap().1, model);
+    }
+
+    #[test]
+    fn rejects_other_versions_and_truncated_files() {
+        let model = import(QUAD.as_bytes(), "quad.obj", &NormalOptions::default()).unwrap();
+        let mut bytes = to_bytes(&Header::default(), &model);
+        assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        bytes[4] += 1;
+        assert!(from_bytes(&bytes).unwrap_err().starts_with("version"));
+    }
+
+    #[test]
+    fn cache_is_rebuilt_when_the_source_changes() {
+        let root = std::env::temp_dir().join(format!("mesh_cache_test_{}", std::process::id()));
+        let cache = root.join(".cache");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("quad.obj"), QUAD).unwrap();
+        let options = NormalOptions::default();
+
+        let model = load(&root, "quad.obj", Some(&cache), &options).unwrap();
+        let cache_file = cache_file(&cache, "quad.obj");
+        assert_eq!(cache_file, cache.join("quad.obj.mesh"));
+        assert!(read_valid(&cache_file, &root.join("quad.obj"), settings_hash(&options)).is_some());
+        // other options need another import
+        let flat = NormalOptions { smooth: false, ..options };
+        assert!(read_valid(&cache_file, &root.join("quad.obj"), settings_hash(&flat)).is_none());
+
+        fs::write(root.join("quad.obj"), QUAD.replace("l 1 2", "")).unwrap();
+        assert!(read_valid(&cache_file, &root.join("quad.obj"), settings_hash(&options)).is_none());
+        let changed = load(&root, "quad.obj", Some(&cache), &options).unwrap();
+        assert_ne!(changed, model);
+        assert_eq!(read(&cache_file).unwrap().1, changed);
+
+        // parse errors name the file under the root
+        fs::write(root.join("quad.obj"), "f 1 2").unwrap();
+        match load(&root, "quad.obj", None, &options) {
+            Err(EngineError::ObjParse { path, .. }) => assert_eq!(path, Some(root.join("quad.obj"))),
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn cache_files_stay_inside_the_cache() {
+        let cache = Path::new("cache");
+        assert_eq!(cache_file(cache, "models/./pawn.obj"), cache.join("models/pawn.obj.mesh"));
+        for path in ["../pawn.obj", "models/../../pawn.obj", "/tmp/pawn.obj", "C:\\pawn.obj", ""].iter() {
+            let file = cache_file(cache, path);
+            assert_eq!(file.parent(), Some(cache), "{}", path);
+        }
+        assert_ne!(cache_file(cache, "../a.obj"), cache_file(cache, "../b.obj"));
+    }
+}
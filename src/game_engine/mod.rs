@@ -2,9 +2,28 @@ pub mod object3d;
 pub mod vertex_types;
 pub mod math;
 pub mod basis;
+pub mod vector2;
 pub mod vector3;
+pub mod vector4;
+pub mod quaternion;
+pub mod matrix3;
+pub mod matrix4;
 pub mod transform;
 pub mod mesh;
 pub mod material;
 pub mod renderer;
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod camera;
+pub mod light;
+pub mod scene;
+pub mod asset_manager;
+pub mod error;
+pub mod texture;
+pub mod mtl;
+pub mod normals;
+pub mod tangents;
+pub mod gltf_import;
+pub mod stl_import;
+pub mod ply_import;
+pub mod mesh_cache;
+pub mod primitives;
\ No newline at end of file
@@ -0,0 +1,148 @@
+use crate::game_engine::color::Color;
+
+// one newmtl block of a wavefront material library
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub ambient: Color,
+    pub diffuse: Color,
+    pub specular: Color,
+    pub emissive: Color,
+    pub shininess: f32,
+    // opacity, 1 is opaque
+    pub dissolve: f32,
+    pub illumination: u32,
+    // paths as written in the file, relative to the library
+    pub diffuse_map: Option<String>,
+    pub bump_map: Option<String>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> MtlMaterial {
+        MtlMaterial {
+            name: name.to_string(),
+            ambient: Color::new(1.0, 1.0, 1.0, 1.0),
+            diffuse: Color::new(0.8, 0.8, 0.8, 1.0),
+            specular: Color::new(0.0, 0.0, 0.0, 1.0),
+            emissive: Color::new(0.0, 0.0, 0.0, 1.0),
+            shininess: 16.0,
+            dissolve: 1.0,
+            illumination: 2,
+            diffuse_map: None,
+            bump_map: None,
+        }
+    }
+
+    // illumination models 0 and 1 have no highlights
+    pub fn has_specular(&self) -> bool {
+        self.illumination >= 2
+    }
+}
+
+// unknown statements are skipped, errors are line number and message
+pub fn parse(source: &str) -> Result<Vec<MtlMaterial>, (usize, String)> {
+    let mut materials = Vec::<MtlMaterial>::new();
+
+    for (line_i, line) in source.lines().enumerate() {
+        let line_number = line_i + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments = words.collect::<Vec<&str>>();
+
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err((line_number, "newmtl without a name".to_string()));
+            }
+            materials.push(MtlMaterial::new(&arguments.join(" ")));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err((line_number, format!("{} before newmtl", keyword))),
+        };
+        let error = |message: &str| (line_number, format!("{}: {}", keyword, message));
+
+        match keyword {
+            "Ka" => material.ambient = parse_color(&arguments).ok_or_else(|| error("expected a color"))?,
+            "Kd" => material.diffuse = parse_color(&arguments).ok_or_else(|| error("expected a color"))?,
+            "Ks" => material.specular = parse_color(&arguments).ok_or_else(|| error("expected a color"))?,
+            "Ke" => material.emissive = parse_color(&arguments).ok_or_else(|| error("expected a color"))?,
+            "Ns" => material.shininess = parse_float(&arguments).ok_or_else(|| error("expected a number"))?,
+            "d" => material.dissolve = parse_float(&arguments).ok_or_else(|| error("expected a number"))?,
+            "Tr" => material.dissolve = 1.0 - parse_float(&arguments).ok_or_else(|| error("expected a number"))?,
+            "illum" => material.illumination = arguments.first()
+                .and_then(|a| a.parse().ok())
+                .ok_or_else(|| error("expected an integer"))?,
+            // map options like -bm come before the file name
+            "map_Kd" => material.diffuse_map = Some(arguments.last().ok_or_else(|| error("expected a file"))?.to_string()),
+            "map_Bump" | "map_bump" | "bump" | "norm" =>
+                material.bump_map = Some(arguments.last().ok_or_else(|| error("expected a file"))?.to_string()),
+            _ => (),
+        }
+    }
+    Ok(materials)
+}
+
+fn parse_float(arguments: &[&str]) -> Option<f32> {
+    arguments.first()?.parse().ok()
+}
+
+// a single value is used for all three channels
+fn parse_color(arguments: &[&str]) -> Option<Color> {
+    let values = arguments.iter().map(|a| a.parse::<f32>().ok()).collect::<Option<Vec<f32>>>()?;
+    match values.len() {
+        1 => Some(Color::new(values[0], values[0], values[0], 1.0)),
+        3 => Some(Color::new(values[0], values[1], values[2], 1.0)),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bundled_library() {
+        let source = std::fs::read_to_string("assets/materials/board.mtl").unwrap();
+        let materials = parse(&source).unwrap();
+        assert_eq!(materials.len(), 3);
+        assert_eq!(materials[0].name, "Material.001");
+        assert_eq!(materials[2].name, "Material_002");
+        assert_eq!(materials[1].diffuse, Color::new(0.64, 0.64, 0.64, 1.0));
+        assert_eq!(materials[1].specular, Color::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(materials[1].shininess, 225.0);
+        assert_eq!(materials[1].illumination, 2);
+    }
+
+    #[test]
+    fn parses_maps_and_transparency() {
+        let source = "
+            # comment
+            newmtl glass
+            Kd 0.1 0.2 0.3
+            Ke 1.0
+            d 0.25
+            illum 1
+            map_Kd textures/glass.png
+            map_Bump -bm 0.5 textures/glass_normal.png
+            Ni 1.5
+        ";
+        let material = &parse(source).unwrap()[0];
+        assert_eq!(material.emissive, Color::new(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(material.dissolve, 0.25);
+        assert!(!material.has_specular());
+        assert_eq!(material.diffuse_map.as_deref(), Some("textures/glass.png"));
+        assert_eq!(material.bump_map.as_deref(), Some("textures/glass_normal.png"));
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        assert_eq!(parse("Kd 1 1 1").unwrap_err().0, 1);
+        assert_eq!(parse("newmtl a\n\nKd 1 x 1").unwrap_err(), (3, "Kd: expected a color".to_string()));
+    }
+}
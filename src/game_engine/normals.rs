@@ -0,0 +1,240 @@
+use wavefront_obj::obj;
+
+use std::collections::HashMap;
+
+use crate::game_engine::vector3::Vector3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NormalOptions {
+    // flat normals give every face its own vertices
+    pub smooth: bool,
+    // faces meeting at a sharper angle (radians) are not smoothed together
+    pub crease_angle: f32,
+    // ignore the normals in the file and always generate them
+    pub recompute: bool,
+}
+
+impl Default for NormalOptions {
+    fn default() -> Self {
+        NormalOptions {
+            smooth: true,
+            crease_angle: 60.0_f32.to_radians(),
+            recompute: false,
+        }
+    }
+}
+
+// triangle of the source object used while averaging
+struct Face<'a> {
+    positions: [usize; 3],
+    // zero for degenerate triangles
+    normal: Vector3,
+    // angle of the triangle at each corner
+    weights: [f32; 3],
+    smoothing_groups: &'a [u32],
+}
+
+// true if some corner has no normal index or if the options ask for new normals
+pub fn needs_normals(object: &obj::Object, options: &NormalOptions) -> bool {
+    options.recompute || object.geometry.iter()
+        .flat_map(|g| g.shapes.iter())
+        .any(|s| match s.primitive {
+            obj::Primitive::Triangle(a, b, c) => a.2.is_none() || b.2.is_none() || c.2.is_none(),
+            obj::Primitive::Line(a, b) => a.2.is_none() || b.2.is_none(),
+            obj::Primitive::Point(a) => a.2.is_none(),
+        })
+}
+
+// copy of the object where every corner references a normal. corners without one get a
+// generated normal, the normals in the file are kept unless the options ask to recompute them.
+// smoothing groups from `s` statements are respected: if the file uses them,
+// faces without a group stay flat, otherwise all faces may be smoothed together.
+// lines and points get an up normal
+pub fn generate_normals(object: &obj::Object, options: &NormalOptions) -> obj::Object {
+    let faces = collect_faces(object);
+    let uses_groups = faces.iter().any(|f| f.smoothing_groups.iter().any(|&g| g != 0));
+    let smooths_with = |a: &Face, b: &Face| {
+        !uses_groups || a.smoothing_groups.iter().any(|&g| g != 0 && b.smoothing_groups.contains(&g))
+    };
+    let crease_cos = options.crease_angle.cos();
+
+    // triangles around every position
+    let mut position_faces = HashMap::<usize, Vec<usize>>::new();
+    for (face_i, face) in faces.iter().enumerate() {
+        for &position in face.positions.iter() {
+            position_faces.entry(position).or_default().push(face_i);
+        }
+    }
+
+    let corner_normal = |face_i: usize, position: usize| -> Vector3 {
+        let face = &faces[face_i];
+        if !options.smooth || !smooths_with(face, face) {
+            return face.normal;
+        }
+        let mut sum = Vector3::default();
+        for &other_i in &position_faces[&position] {
+            let other = &faces[other_i];
+            if other_i != face_i && (!smooths_with(face, other) || face.normal.dot(other.normal) < crease_cos) {
+                continue;
+            }
+            let slot = other.positions.iter().position(|&p| p == position).unwrap_or(0);
+            sum += other.normal * other.weights[slot];
+        }
+        if sum.length_squared() > 0.0 { sum.normalized() } else { face.normal }
+    };
+
+    let mut result = object.clone();
+    if options.recompute {
+        result.normals.clear();
+    }
+    let keep = |normal: Option<usize>| if options.recompute { None } else { normal };
+    let up = push_normal(&mut result.normals, Vector3::new(0.0, 1.0, 0.0));
+    let mut face_i = 0;
+    for shape in result.geometry.iter_mut().flat_map(|g| g.shapes.iter_mut()) {
+        shape.primitive = match shape.primitive {
+            obj::Primitive::Triangle(mut a, mut b, mut c) => {
+                for corner in [&mut a, &mut b, &mut c].iter_mut() {
+                    if keep(corner.2).is_some() {
+                        continue;
+                    }
+                    let normal = corner_normal(face_i, corner.0);
+                    corner.2 = Some(if normal.length_squared() > 0.0 { push_normal(&mut result.normals, normal) } else { up });
+                }
+                face_i += 1;
+                obj::Primitive::Triangle(a, b, c)
+            },
+            obj::Primitive::Line(a, b) =>
+                obj::Primitive::Line((a.0, a.1, keep(a.2).or(Some(up))), (b.0, b.1, keep(b.2).or(Some(up)))),
+            obj::Primitive::Point(a) => obj::Primitive::Point((a.0, a.1, keep(a.2).or(Some(up)))),
+        };
+    }
+    result
+}
+
+fn collect_faces(object: &obj::Object) -> Vec<Face<'_>> {
+    let position = |i: usize| {
+        let v = object.vertices[i];
+        Vector3::new(v.x as f32, v.y as f32, v.z as f32)
+    };
+    let mut faces = Vec::new();
+    for shape in object.geometry.iter().flat_map(|g| g.shapes.iter()) {
+        if let obj::Primitive::Triangle(a, b, c) = shape.primitive {
+            let positions = [a.0, b.0, c.0];
+            let corners = [position(a.0), position(b.0), position(c.0)];
+            let cross = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+            let degenerate = cross.length_squared() == 0.0;
+            let mut weights = [0.0; 3];
+            if !degenerate {
+                for (i, weight) in weights.iter_mut().enumerate() {
+                    *weight = (corners[(i + 1) % 3] - corners[i]).angle_to(corners[(i + 2) % 3] - corners[i]);
+                }
+            }
+            faces.push(Face {
+                positions,
+                normal: if degenerate { Vector3::default() } else { cross.normalized() },
+                weights,
+                smoothing_groups: &shape.smoothing_groups,
+            });
+        }
+    }
+    faces
+}
+
+fn push_normal(normals: &mut Vec<obj::Normal>, normal: Vector3) -> usize {
+    normals.push(obj::Normal { x: normal.x as f64, y: normal.y as f64, z: normal.z as f64 });
+    normals.len() - 1
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two quads folded 90 degrees along the z axis, sharing the edge at x = 0
+    const FOLD: &str = "
+        v 0 0 0
+        v 0 0 1
+        v 1 0 0
+        v 1 0 1
+        v 0 1 0
+        v 0 1 1
+        f 1 2 4
+        f 1 4 3
+        f 1 6 2
+        f 1 5 6
+    ";
+
+    fn normal_at(object: &obj::Object, face: usize, position: usize) -> Vector3 {
+        let shape = &object.geometry[0].shapes[face];
+        let corners = match shape.primitive {
+            obj::Primitive::Triangle(a, b, c) => [a, b, c],
+            _ => panic!("not a triangle"),
+        };
+        let corner = corners.iter().find(|c| c.0 == position).unwrap();
+        let n = object.normals[corner.2.unwrap()];
+        Vector3::new(n.x as f32, n.y as f32, n.z as f32)
+    }
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).length() < 1.0e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn missing_normals_are_detected() {
+        let object = &obj::parse(FOLD).unwrap().objects[0];
+        assert!(needs_normals(object, &NormalOptions::default()));
+        let generated = generate_normals(object, &NormalOptions::default());
+        assert!(!needs_normals(&generated, &NormalOptions::default()));
+        assert!(needs_normals(&generated, &NormalOptions { recompute: true, ..Default::default() }));
+    }
+
+    #[test]
+    fn authored_normals_are_kept() {
+        // the first quad has a tilted normal in the file, the second has none
+        let source = FOLD.replace("v 0 0 0", "v 0 0 0\n vn 0 0.6 0.8")
+            .replace("f 1 2 4", "f 1//1 2//1 4//1").replace("f 1 4 3", "f 1//1 4//1 3//1");
+        let object = &obj::parse(source).unwrap().objects[0];
+        assert!(needs_normals(object, &NormalOptions::default()));
+        let filled = generate_normals(object, &NormalOptions::default());
+        assert!(!needs_normals(&filled, &NormalOptions::default()));
+        assert_near(normal_at(&filled, 0, 0), Vector3::new(0.0, 0.6, 0.8));
+        assert_near(normal_at(&filled, 1, 2), Vector3::new(0.0, 0.6, 0.8));
+        assert_near(normal_at(&filled, 2, 5), Vector3::new(1.0, 0.0, 0.0));
+
+        let recomputed = generate_normals(object, &NormalOptions { recompute: true, ..Default::default() });
+        assert_near(normal_at(&recomputed, 0, 0), Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn flat_and_crease_keep_face_normals() {
+        let object = &obj::parse(FOLD).unwrap().objects[0];
+        let flat = generate_normals(object, &NormalOptions { smooth: false, ..Default::default() });
+        assert_near(normal_at(&flat, 0, 0), Vector3::new(0.0, 1.0, 0.0));
+        assert_near(normal_at(&flat, 2, 0), Vector3::new(1.0, 0.0, 0.0));
+
+        // the fold is sharper than the default crease angle
+        let creased = generate_normals(object, &NormalOptions::default());
+        assert_near(normal_at(&creased, 0, 0), Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn smooth_normals_are_angle_weighted() {
+        let object = &obj::parse(FOLD).unwrap().objects[0];
+        let options = NormalOptions { crease_angle: std::f32::consts::PI, ..Default::default() };
+        let smooth = generate_normals(object, &options);
+        // both faces meet the shared vertex with the same total angle
+        let expected = Vector3::new(1.0, 1.0, 0.0).normalized();
+        assert_near(normal_at(&smooth, 0, 0), expected);
+        assert_near(normal_at(&smooth, 3, 0), expected);
+    }
+
+    #[test]
+    fn smoothing_groups_split_faces() {
+        let source = FOLD.replace("f 1 2 4", "s 1\n f 1 2 4").replace("f 1 6 2", "s 2\n f 1 6 2");
+        let object = &obj::parse(source).unwrap().objects[0];
+        let options = NormalOptions { crease_angle: std::f32::consts::PI, ..Default::default() };
+        let grouped = generate_normals(object, &options);
+        assert_near(normal_at(&grouped, 0, 0), Vector3::new(0.0, 1.0, 0.0));
+        assert_near(normal_at(&grouped, 2, 0), Vector3::new(1.0, 0.0, 0.0));
+    }
+}
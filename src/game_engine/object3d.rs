@@ -1,22 +1,171 @@
-use wavefront_obj::obj;
-
 use std::rc::Rc;
 
-use crate::game_engine::vertex_types::VertexPN;
 use crate::game_engine::transform::Transform;
-use crate::game_engine::mesh::Mesh;
+use crate::game_engine::mesh::GpuMesh;
 use crate::game_engine::material::Material;
 
+// node of the scene tree, children are positioned relative to their parent
 pub struct Object3D {
-    pub mesh: Mesh,
-    pub transform: Transform,
+    pub name: String,
+    pub mesh: Option<Rc<GpuMesh>>,
+    // one material per mesh surface
+    pub materials: Vec<Material>,
+    transform: Transform,
+    global_transform: Transform,
+    transform_changed: bool,
+    children: Vec<Object3D>,
 }
 
 impl Object3D {
-    pub fn new(mesh: Mesh) -> Object3D {        
+    pub fn new(name: &str) -> Object3D {
         Object3D {
-            mesh,
+            name: name.to_string(),
+            mesh: None,
+            materials: Vec::new(),
             transform: Transform::new(),
+            global_transform: Transform::new(),
+            transform_changed: true,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_mesh(name: &str, mesh: Rc<GpuMesh>, materials: Vec<Material>) -> Object3D {
+        let mut object = Object3D::new(name);
+        object.mesh = Some(mesh);
+        object.materials = materials;
+        object
+    }
+
+    // local transform, relative to the parent
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn get_transform_mut(&mut self) -> &mut Transform {
+        self.transform_changed = true;
+        &mut self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform_changed = true;
+        self.transform = transform;
+    }
+
+    // cached world transform, valid after update_global_transforms
+    pub fn get_global_transform(&self) -> &Transform {
+        &self.global_transform
+    }
+
+    pub fn add_child(&mut self, mut child: Object3D) -> &mut Object3D {
+        child.transform_changed = true;
+        self.children.push(child);
+        self.children.last_mut().unwrap()
+    }
+
+    pub fn remove_child(&mut self, name: &str) -> Option<Object3D> {
+        let index = self.children.iter().position(|c| c.name == name)?;
+        Some(self.children.remove(index))
+    }
+
+    pub fn get_children(&self) -> &[Object3D] {
+        &self.children
+    }
+
+    pub fn get_children_mut(&mut self) -> &mut [Object3D] {
+        &mut self.children
+    }
+
+    // depth-first search through the whole subtree, self included
+    pub fn find(&self, name: &str) -> Option<&Object3D> {
+        self.iter().find(|o| o.name == name)
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Object3D> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.find_mut(name))
+    }
+
+    // depth-first, parents before children
+    pub fn iter(&self) -> Object3DIter<'_> {
+        Object3DIter { stack: vec!(self) }
+    }
+
+    // treats self as the root of the tree
+    pub fn update_global_transforms(&mut self) {
+        self.update_global_transform(&Transform::new(), false);
+    }
+
+    fn update_global_transform(&mut self, parent: &Transform, parent_changed: bool) {
+        let changed = parent_changed || self.transform_changed;
+        if changed {
+            self.global_transform = *parent * self.transform;
+            self.transform_changed = false;
+        }
+        let global_transform = self.global_transform;
+        for child in self.children.iter_mut() {
+            child.update_global_transform(&global_transform, changed);
         }
     }
-}
\ No newline at end of file
+}
+
+pub struct Object3DIter<'a> {
+    stack: Vec<&'a Object3D>,
+}
+
+impl<'a> Iterator for Object3DIter<'a> {
+    type Item = &'a Object3D;
+    fn next(&mut self) -> Option<Self::Item> {
+        let object = self.stack.pop()?;
+        self.stack.extend(object.children.iter().rev());
+        Some(object)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_engine::vector3::Vector3;
+
+    fn board_with_king() -> Object3D {
+        let mut root = Object3D::new("root");
+        let board = root.add_child(Object3D::new("board"));
+        board.get_transform_mut().set_position(Vector3::new(10.0, 0.0, 0.0));
+        let king = board.add_child(Object3D::new("king"));
+        king.get_transform_mut().set_position(Vector3::new(1.0, 0.0, 0.0));
+        king.add_child(Object3D::new("crown"))
+            .get_transform_mut().set_position(Vector3::new(0.0, 2.0, 0.0));
+        root.add_child(Object3D::new("camera"));
+        root
+    }
+
+    #[test]
+    fn global_transforms_follow_parents() {
+        let mut root = board_with_king();
+        root.update_global_transforms();
+        assert_eq!(root.find("crown").unwrap().get_global_transform().get_position(), Vector3::new(11.0, 2.0, 0.0));
+
+        root.find_mut("board").unwrap().get_transform_mut().set_position(Vector3::new(-5.0, 0.0, 0.0));
+        root.update_global_transforms();
+        assert_eq!(root.find("king").unwrap().get_global_transform().get_position(), Vector3::new(-4.0, 0.0, 0.0));
+        assert_eq!(root.find("crown").unwrap().get_global_transform().get_position(), Vector3::new(-4.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn iter_is_depth_first() {
+        let root = board_with_king();
+        let names = root.iter().map(|o| o.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!("root", "board", "king", "crown", "camera"));
+    }
+
+    #[test]
+    fn remove_child_detaches_subtree() {
+        let mut root = board_with_king();
+        let board = root.remove_child("board").unwrap();
+        assert_eq!(board.iter().count(), 3);
+        assert!(root.find("crown").is_none());
+        assert!(root.remove_child("board").is_none());
+    }
+}
@@ -0,0 +1,382 @@
+use wavefront_obj::obj;
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::str;
+
+use crate::game_engine::color::Color;
+use crate::game_engine::error::EngineError;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // integer colors use the whole range of their type, float colors are already 0 to 1
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(String, Scalar),
+    // count type, item type
+    List(String, Scalar, Scalar),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// values of the body, whitespace separated numbers or packed binary
+struct Reader<'a> {
+    format: Format,
+    body: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        if self.format == Format::Ascii {
+            let rest = &self.body[self.position..];
+            let start = rest.iter().position(|b| !b.is_ascii_whitespace()).ok_or("unexpected end of file")?;
+            let length = rest[start..].iter().position(|b| b.is_ascii_whitespace()).unwrap_or(rest.len() - start);
+            self.position += start + length;
+            let word = str::from_utf8(&rest[start..start + length]).map_err(|e| e.to_string())?;
+            return word.parse::<f64>().map_err(|_| format!("'{}' is not a number", word));
+        }
+
+        let bytes = self.body.get(self.position..self.position + scalar.size()).ok_or("unexpected end of file")?;
+        self.position += scalar.size();
+        let big_endian = self.format == Format::BigEndian;
+        macro_rules! number {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                (if big_endian { <$t>::from_be_bytes(bytes) } else { <$t>::from_le_bytes(bytes) }) as f64
+            }};
+        }
+        Ok(match scalar {
+            Scalar::I8 => number!(i8),
+            Scalar::U8 => number!(u8),
+            Scalar::I16 => number!(i16),
+            Scalar::U16 => number!(u16),
+            Scalar::I32 => number!(i32),
+            Scalar::U32 => number!(u32),
+            Scalar::F32 => number!(f32),
+            Scalar::F64 => number!(f64),
+        })
+    }
+}
+
+// the object is named after the file
+pub fn import(file: &Path) -> Result<(obj::Object, Vec<Color>), EngineError> {
+    let bytes = fs::read(file).map_err(|e| EngineError::io(file, e))?;
+    let name = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    parse(&bytes, &name).map_err(|e| e.with_path(file))
+}
+
+// ascii or binary ply. the object has one vertex per ply vertex, colors are indexed
+// the same way and empty if the file has none. faces are triangulated as fans, files
+// without faces or edges give a point per vertex
+pub fn parse(bytes: &[u8], name: &str) -> Result<(obj::Object, Vec<Color>), EngineError> {
+    parse_ply(bytes, name).map_err(|message| EngineError::ModelParse { path: None, message })
+}
+
+fn parse_ply(bytes: &[u8], name: &str) -> Result<(obj::Object, Vec<Color>), String> {
+    let (format, elements, body) = parse_header(bytes)?;
+    let mut reader = Reader { format, body, position: 0 };
+
+    let mut object = obj::Object {
+        name: name.to_string(),
+        vertices: Vec::new(),
+        tex_vertices: Vec::new(),
+        normals: Vec::new(),
+        geometry: Vec::new(),
+    };
+    let mut colors = Vec::new();
+    let mut faces = Vec::<Vec<usize>>::new();
+    let mut edges = Vec::<[usize; 2]>::new();
+    let mut has_connectivity = false;
+
+    for element in &elements {
+        let find = |names: &[&str]| element.properties.iter().position(|p| match p {
+            Property::Scalar(name, _) => names.contains(&name.as_str()),
+            Property::List(..) => false,
+        });
+        let color_scale = |i: Option<usize>| match i.map(|i| &element.properties[i]) {
+            Some(Property::Scalar(_, scalar)) => scalar.color_scale(),
+            _ => 1.0,
+        };
+        let (x, y, z) = (find(&["x"]), find(&["y"]), find(&["z"]));
+        let normal = (find(&["nx"]), find(&["ny"]), find(&["nz"]));
+        let uv = (find(&["u", "s", "texture_u", "texture_s"]), find(&["v", "t", "texture_v", "texture_t"]));
+        let rgb = (find(&["red", "r"]), find(&["green", "g"]), find(&["blue", "b"]));
+        let alpha = find(&["alpha", "a"]);
+        let list = element.properties.iter().position(|p| match p {
+            Property::List(name, _, _) => name == "vertex_indices" || name == "vertex_index",
+            Property::Scalar(..) => false,
+        });
+        let edge = (find(&["vertex1"]), find(&["vertex2"]));
+
+        if element.name == "vertex" && (x.is_none() || y.is_none() || z.is_none()) {
+            return Err("vertex element without x, y and z".to_string());
+        }
+        // a point cloud may still declare an empty face element
+        if (element.name == "face" || element.name == "edge") && element.count > 0 {
+            has_connectivity = true;
+        }
+
+        for _ in 0..element.count {
+            let mut scalars = Vec::with_capacity(element.properties.len());
+            let mut lists = Vec::new();
+            for property in &element.properties {
+                match *property {
+                    Property::Scalar(_, scalar) => {
+                        scalars.push(reader.read(scalar)?);
+                        lists.push(Vec::new());
+                    },
+                    Property::List(_, count, item) => {
+                        let length = reader.read(count)? as usize;
+                        scalars.push(length as f64);
+                        lists.push((0..length).map(|_| reader.read(item)).collect::<Result<Vec<f64>, String>>()?);
+                    },
+                }
+            }
+            let value = |i: Option<usize>| i.map(|i| scalars[i]).unwrap_or(0.0);
+
+            match element.name.as_str() {
+                "vertex" => {
+                    object.vertices.push(obj::Vertex { x: value(x), y: value(y), z: value(z) });
+                    if let (Some(_), Some(_), Some(_)) = normal {
+                        object.normals.push(obj::Vertex { x: value(normal.0), y: value(normal.1), z: value(normal.2) });
+                    }
+                    if let (Some(_), Some(_)) = uv {
+                        object.tex_vertices.push(obj::TVertex { u: value(uv.0), v: value(uv.1), w: 0.0 });
+                    }
+                    if let (Some(_), Some(_), Some(_)) = rgb {
+                        let channel = |i: Option<usize>| (value(i) / color_scale(i)) as f32;
+                        let a = if alpha.is_some() { channel(alpha) } else { 1.0 };
+                        colors.push(Color::new(channel(rgb.0), channel(rgb.1), channel(rgb.2), a));
+                    }
+                },
+                "face" => if let Some(list) = list {
+                    faces.push(lists[list].iter().map(|&i| i as usize).collect());
+                },
+                "edge" => if let (Some(_), Some(_)) = edge {
+                    edges.push([value(edge.0) as usize, value(edge.1) as usize]);
+                },
+                _ => (),
+            }
+        }
+    }
+
+    let vertex_count = object.vertices.len();
+    if let Some(&index) = faces.iter().flatten().chain(edges.iter().flatten()).find(|&&i| i >= vertex_count) {
+        return Err(format!("index {} but {} vertices", index, vertex_count));
+    }
+    let has_normals = !object.normals.is_empty();
+    let has_uvs = !object.tex_vertices.is_empty();
+    let corner = |i: usize| (i, if has_uvs { Some(i) } else { None }, if has_normals { Some(i) } else { None });
+    let shape = |primitive| obj::Shape { primitive, groups: Vec::new(), smoothing_groups: Vec::new() };
+
+    let mut shapes = Vec::new();
+    for face in &faces {
+        match face.len() {
+            0 => (),
+            1 => shapes.push(shape(obj::Primitive::Point(corner(face[0])))),
+            2 => shapes.push(shape(obj::Primitive::Line(corner(face[0]), corner(face[1])))),
+            _ => for pair in face[1..].windows(2) {
+                shapes.push(shape(obj::Primitive::Triangle(corner(face[0]), corner(pair[0]), corner(pair[1]))));
+            },
+        }
+    }
+    shapes.extend(edges.iter().map(|e| shape(obj::Primitive::Line(corner(e[0]), corner(e[1])))));
+    if !has_connectivity {
+        shapes.extend((0..vertex_count).map(|i| shape(obj::Primitive::Point(corner(i)))));
+    }
+    object.geometry.push(obj::Geometry { material_name: None, shapes });
+    Ok((object, colors))
+}
+
+// format, elements and the bytes after end_header
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, &[u8]), String> {
+    let mut format = None;
+    let mut elements = Vec::<Element>::new();
+    let mut position = 0;
+    let mut line_number = 0;
+    loop {
+        let length = bytes[position..].iter().position(|&b| b == b'\n').ok_or("header without end_header")?;
+        let line = str::from_utf8(&bytes[position..position + length]).map_err(|e| e.to_string())?.trim();
+        position += length + 1;
+        line_number += 1;
+        let error = |message: &str| format!("header line {}: {}", line_number, message);
+
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            ["ply"] if line_number == 1 => (),
+            _ if line_number == 1 => return Err("not a ply file".to_string()),
+            ["format", name, _version] => format = Some(match *name {
+                "ascii" => Format::Ascii,
+                "binary_little_endian" => Format::LittleEndian,
+                "binary_big_endian" => Format::BigEndian,
+                _ => return Err(error("unknown format")),
+            }),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error("expected an element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let count = Scalar::parse(count).ok_or_else(|| error("unknown type"))?;
+                let item = Scalar::parse(item).ok_or_else(|| error("unknown type"))?;
+                elements.last_mut().ok_or_else(|| error("property before element"))?
+                    .properties.push(Property::List(name.to_string(), count, item));
+            },
+            ["property", scalar, name] => {
+                let scalar = Scalar::parse(scalar).ok_or_else(|| error("unknown type"))?;
+                elements.last_mut().ok_or_else(|| error("property before element"))?
+                    .properties.push(Property::Scalar(name.to_string(), scalar));
+            },
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            _ => return Err(error("unknown statement")),
+        }
+    }
+    let format = format.ok_or("header without format")?;
+    Ok((format, elements, &bytes[position..]))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_engine::mesh::MeshData;
+    use crate::game_engine::normals::NormalOptions;
+
+    #[test]
+    fn parses_ascii_quad_with_colors() {
+        let source = "ply
+format ascii 1.0
+comment scanned
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+        let (object, colors) = parse(source.as_bytes(), "quad").unwrap();
+        assert_eq!(object.vertices.len(), 4);
+        assert!(object.normals.is_empty());
+        assert_eq!(colors[1], Color::new(0.0, 1.0, 0.0, 1.0));
+        let triangles = object.geometry[0].shapes.iter().map(|s| match s.primitive {
+            obj::Primitive::Triangle(a, b, c) => [a.0, b.0, c.0],
+            _ => panic!("not a triangle"),
+        }).collect::<Vec<_>>();
+        assert_eq!(triangles, vec!([0, 1, 2], [0, 2, 3]));
+
+        // colors follow the vertices through deduplication
+        let data = MeshData::from_object(&object, &colors, &NormalOptions::default()).unwrap();
+        assert_eq!(data.vertices.len(), 4);
+        assert_eq!(data.colors[3].color, (1.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn parses_binary_point_cloud() {
+        let header = "ply\nformat binary_big_endian 1.0\nelement vertex 2\nproperty double x\nproperty double y\n\
+            property double z\nproperty float nx\nproperty float ny\nproperty float nz\nend_header\n";
+        let mut bytes = header.as_bytes().to_vec();
+        for vertex in [[1.0f64, 2.0, 3.0], [4.0, 5.0, 6.0]].iter() {
+            for value in vertex.iter() {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+            for value in [0.0f32, 1.0, 0.0].iter() {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        let (object, colors) = parse(&bytes, "scan").unwrap();
+        assert!(colors.is_empty());
+        assert_eq!(object.vertices[1], obj::Vertex { x: 4.0, y: 5.0, z: 6.0 });
+        assert_eq!(object.normals[0], obj::Vertex { x: 0.0, y: 1.0, z: 0.0 });
+        assert_eq!(object.geometry[0].shapes[1].primitive, obj::Primitive::Point((1, None, Some(1))));
+    }
+
+    #[test]
+    fn empty_face_element_is_a_point_cloud() {
+        let source = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+            element face 0\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 2 3\n";
+        let (object, _) = parse(source.as_bytes(), "scan").unwrap();
+        assert_eq!(object.geometry[0].shapes.len(), 2);
+        assert_eq!(object.geometry[0].shapes[1].primitive, obj::Primitive::Point((1, None, None)));
+    }
+
+    #[test]
+    fn reports_bad_files() {
+        assert!(parse(b"solid cube\n", "cube").is_err());
+        let truncated = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0 0\n";
+        match parse(truncated.as_bytes(), "cube") {
+            Err(EngineError::ModelParse { message, .. }) => assert_eq!(message, "unexpected end of file"),
+            _ => panic!("truncated file accepted"),
+        }
+    }
+}
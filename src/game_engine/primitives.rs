@@ -0,0 +1,330 @@
+use wavefront_obj::obj;
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use crate::game_engine::vector3::Vector3;
+
+// generated shapes are objects like imported ones, with normals and uvs, centered on the origin
+// with y up and counter clockwise front faces. MeshData::from_object turns them into
+// the same vertices and indices as a file with the same faces
+
+// a grid in the xz plane facing +y, uvs span the whole plane
+pub fn plane(width: f32, depth: f32, columns: usize, rows: usize) -> obj::Object {
+    let mut builder = Builder::new("plane", 1);
+    builder.patch(0, Vector3::new(0.0, 0.0, 0.0), Vector3::new(width, 0.0, 0.0), Vector3::new(0.0, 0.0, -depth), columns, rows);
+    builder.object
+}
+
+// squares x squares cells alternating between the materials "light" and "dark",
+// the corner at -x -z is dark like a1 on a chessboard
+pub fn checkerboard(size: f32, squares: usize) -> obj::Object {
+    let mut builder = Builder::new("checkerboard", 2);
+    builder.object.geometry[0].material_name = Some("light".to_string());
+    builder.object.geometry[1].material_name = Some("dark".to_string());
+    let cell = size / squares.max(1) as f32;
+    for column in 0..squares {
+        for row in 0..squares {
+            let center = Vector3::new((column as f32 + 0.5) * cell - size / 2.0, 0.0, (row as f32 + 0.5) * cell - size / 2.0);
+            let geometry = if (column + row) % 2 == 0 { 1 } else { 0 };
+            builder.patch(geometry, center, Vector3::new(cell, 0.0, 0.0), Vector3::new(0.0, 0.0, -cell), 1, 1);
+        }
+    }
+    builder.object
+}
+
+// every face is a subdivisions x subdivisions grid with its own 0..1 uvs
+pub fn cube(size: f32, subdivisions: usize) -> obj::Object {
+    let mut builder = Builder::new("cube", 1);
+    let (x, y, z) = (Vector3::new(size, 0.0, 0.0), Vector3::new(0.0, size, 0.0), Vector3::new(0.0, 0.0, size));
+    // outward direction, then the right and up axes of the face seen from outside
+    let faces = [(x, -z, y), (-x, z, y), (y, x, -z), (-y, x, z), (z, x, y), (-z, -x, y)];
+    for &(out, right, up) in faces.iter() {
+        builder.patch(0, out * 0.5, right, up, subdivisions, subdivisions);
+    }
+    builder.object
+}
+
+// segments around the y axis, rings from pole to pole. u follows the longitude, v goes up
+pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> obj::Object {
+    let mut builder = Builder::new("uv_sphere", 1);
+    let (segments, rings) = (segments.max(3), rings.max(2));
+    builder.grid(0, segments, rings, |column, row| {
+        let (u, v) = (column as f32 / segments as f32, row as f32 / rings as f32);
+        let normal = direction(u * 2.0 * PI, v * PI - PI / 2.0);
+        (normal * radius, normal, (u, v))
+    });
+    builder.object
+}
+
+// an icosahedron whose triangles are split in four subdivisions times, uvs are spherical
+pub fn icosphere(radius: f32, subdivisions: usize) -> obj::Object {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut points = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ].iter().map(|&p| Vector3::from(p).normalized()).collect::<Vec<_>>();
+    let mut triangles = vec!(
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    );
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::<(usize, usize), usize>::new();
+        let mut midpoint = |a: usize, b: usize, points: &mut Vec<Vector3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push(((points[a] + points[b]) * 0.5).normalized());
+                points.len() - 1
+            })
+        };
+        triangles = triangles.iter().flat_map(|&[a, b, c]| {
+            let (ab, bc, ca) = (midpoint(a, b, &mut points), midpoint(b, c, &mut points), midpoint(c, a, &mut points));
+            vec!([a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca])
+        }).collect();
+    }
+
+    let mut builder = Builder::new("icosphere", 1);
+    for triangle in triangles {
+        let normals = [points[triangle[0]], points[triangle[1]], points[triangle[2]]];
+        let mut us = normals.iter().map(|n| 0.5 + n.x.atan2(n.z) / (2.0 * PI)).collect::<Vec<_>>();
+        // triangles crossing the seam wrap around instead of spanning the whole texture
+        let max_u = us.iter().cloned().fold(0.0, f32::max);
+        for u in us.iter_mut() {
+            if max_u - *u > 0.5 {
+                *u += 1.0;
+            }
+        }
+        // the longitude of a pole is the one of the rest of the triangle
+        for i in 0..3 {
+            if normals[i].x.abs() < 1.0e-6 && normals[i].z.abs() < 1.0e-6 {
+                us[i] = (us[(i + 1) % 3] + us[(i + 2) % 3]) / 2.0;
+            }
+        }
+        let corners = (0..3).map(|i| {
+            let v = 0.5 + normals[i].y.asin() / PI;
+            builder.vertex(normals[i] * radius, normals[i], (us[i], v))
+        }).collect::<Vec<_>>();
+        builder.triangle(0, corners[0], corners[1], corners[2]);
+    }
+    builder.object
+}
+
+// closed at both ends, rings split the side along its height
+pub fn cylinder(radius: f32, height: f32, segments: usize, rings: usize) -> obj::Object {
+    frustum("cylinder", radius, radius, height, segments, rings)
+}
+
+// apex at +y, base at -y
+pub fn cone(radius: f32, height: f32, segments: usize, rings: usize) -> obj::Object {
+    frustum("cone", radius, 0.0, height, segments, rings)
+}
+
+// a cylinder of the given height with hemispheres of rings rings at both ends
+pub fn capsule(radius: f32, height: f32, segments: usize, rings: usize) -> obj::Object {
+    let mut builder = Builder::new("capsule", 1);
+    let (segments, rings) = (segments.max(3), rings.max(1));
+    let total_height = height + 2.0 * radius;
+    builder.grid(0, segments, 2 * rings + 1, |column, row| {
+        let u = column as f32 / segments as f32;
+        // rows up to rings are the lower hemisphere, the row after it starts the upper one
+        let (latitude, offset) = if row <= rings {
+            (row as f32 / rings as f32 * PI / 2.0 - PI / 2.0, -height / 2.0)
+        } else {
+            ((row - rings - 1) as f32 / rings as f32 * PI / 2.0, height / 2.0)
+        };
+        let normal = direction(u * 2.0 * PI, latitude);
+        let position = normal * radius + Vector3::new(0.0, offset, 0.0);
+        (position, normal, (u, (position.y + total_height / 2.0) / total_height))
+    });
+    builder.object
+}
+
+// a ring around the y axis, segments around the ring and sides around the tube
+pub fn torus(major_radius: f32, minor_radius: f32, segments: usize, sides: usize) -> obj::Object {
+    let mut builder = Builder::new("torus", 1);
+    let (segments, sides) = (segments.max(3), sides.max(3));
+    builder.grid(0, segments, sides, |column, row| {
+        let (u, v) = (column as f32 / segments as f32, row as f32 / sides as f32);
+        let normal = direction(u * 2.0 * PI, v * 2.0 * PI);
+        let center = direction(u * 2.0 * PI, 0.0) * major_radius;
+        (center + normal * minor_radius, normal, (u, v))
+    });
+    builder.object
+}
+
+fn frustum(name: &str, bottom_radius: f32, top_radius: f32, height: f32, segments: usize, rings: usize) -> obj::Object {
+    let mut builder = Builder::new(name, 1);
+    let (segments, rings) = (segments.max(3), rings.max(1));
+    builder.grid(0, segments, rings, |column, row| {
+        let (u, v) = (column as f32 / segments as f32, row as f32 / rings as f32);
+        let around = direction(u * 2.0 * PI, 0.0);
+        let radius = bottom_radius + (top_radius - bottom_radius) * v;
+        let normal = (around * height + Vector3::new(0.0, bottom_radius - top_radius, 0.0)).normalized();
+        (around * radius + Vector3::new(0.0, height * (v - 0.5), 0.0), normal, (u, v))
+    });
+
+    for &(radius, y, up) in [(bottom_radius, -height / 2.0, -1.0), (top_radius, height / 2.0, 1.0)].iter() {
+        if radius <= 0.0 {
+            continue;
+        }
+        let normal = Vector3::new(0.0, up, 0.0);
+        let center = builder.vertex(Vector3::new(0.0, y, 0.0), normal, (0.5, 0.5));
+        let ring = (0..=segments).map(|i| {
+            let around = direction(i as f32 / segments as f32 * 2.0 * PI, 0.0);
+            builder.vertex(around * radius + Vector3::new(0.0, y, 0.0), normal, (0.5 + around.x * 0.5, 0.5 - around.z * 0.5 * up))
+        }).collect::<Vec<_>>();
+        for pair in ring.windows(2) {
+            if up > 0.0 {
+                builder.triangle(0, center, pair[0], pair[1]);
+            } else {
+                builder.triangle(0, center, pair[1], pair[0]);
+            }
+        }
+    }
+    builder.object
+}
+
+// unit vector at longitude around the y axis (0 is +z, a quarter turn is +x) and latitude above the xz plane
+fn direction(longitude: f32, latitude: f32) -> Vector3 {
+    // exact at the poles so the triangles touching them collapse
+    let cos = if (latitude.abs() - PI / 2.0).abs() < 1.0e-6 { 0.0 } else { latitude.cos() };
+    Vector3::new(cos * longitude.sin(), latitude.sin(), cos * longitude.cos())
+}
+
+struct Builder {
+    object: obj::Object,
+}
+
+impl Builder {
+    fn new(name: &str, geometry_count: usize) -> Builder {
+        Builder {
+            object: obj::Object {
+                name: name.to_string(),
+                vertices: Vec::new(),
+                tex_vertices: Vec::new(),
+                normals: Vec::new(),
+                geometry: (0..geometry_count).map(|_| obj::Geometry { material_name: None, shapes: Vec::new() }).collect(),
+            },
+        }
+    }
+
+    // every vertex gets its own position, uv and normal, equal ones are merged on upload
+    fn vertex(&mut self, position: Vector3, normal: Vector3, uv: (f32, f32)) -> usize {
+        let object = &mut self.object;
+        object.vertices.push(obj::Vertex { x: position.x as f64, y: position.y as f64, z: position.z as f64 });
+        object.normals.push(obj::Vertex { x: normal.x as f64, y: normal.y as f64, z: normal.z as f64 });
+        object.tex_vertices.push(obj::TVertex { u: uv.0 as f64, v: uv.1 as f64, w: 0.0 });
+        object.vertices.len() - 1
+    }
+
+    // triangles collapsed to a line, like the ones touching a pole, are dropped
+    fn triangle(&mut self, geometry: usize, a: usize, b: usize, c: usize) {
+        let position = |i: usize| self.object.vertices[i];
+        let same = |i: usize, j: usize| (position(i).x, position(i).y, position(i).z) == (position(j).x, position(j).y, position(j).z);
+        if same(a, b) || same(b, c) || same(c, a) {
+            return;
+        }
+        let corner = |i| (i, Some(i), Some(i));
+        self.object.geometry[geometry].shapes.push(obj::Shape {
+            primitive: obj::Primitive::Triangle(corner(a), corner(b), corner(c)),
+            groups: Vec::new(),
+            smoothing_groups: Vec::new(),
+        });
+    }
+
+    // (columns + 1) x (rows + 1) vertices from vertex(column, row), the u direction crossed with
+    // the v direction faces the front
+    fn grid(&mut self, geometry: usize, columns: usize, rows: usize,
+        vertex: impl Fn(usize, usize) -> (Vector3, Vector3, (f32, f32))) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let first = self.object.vertices.len();
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (position, normal, uv) = vertex(column, row);
+                self.vertex(position, normal, uv);
+            }
+        }
+        let index = |column: usize, row: usize| first + row * (columns + 1) + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                let (a, b) = (index(column, row), index(column + 1, row));
+                let (c, d) = (index(column + 1, row + 1), index(column, row + 1));
+                self.triangle(geometry, a, b, c);
+                self.triangle(geometry, a, c, d);
+            }
+        }
+    }
+
+    // a flat rectangle around center spanning right and up, facing right x up
+    fn patch(&mut self, geometry: usize, center: Vector3, right: Vector3, up: Vector3, columns: usize, rows: usize) {
+        let normal = right.cross(up).normalized();
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        self.grid(geometry, columns, rows, |column, row| {
+            let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+            (center + right * (u - 0.5) + up * (v - 0.5), normal, (u, v))
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_engine::mesh::MeshData;
+    use crate::game_engine::normals::NormalOptions;
+    use crate::game_engine::vertex_types::VertexFormat;
+
+    fn all() -> Vec<obj::Object> {
+        vec!(
+            plane(2.0, 1.0, 4, 2), checkerboard(8.0, 8), cube(1.0, 2), uv_sphere(1.0, 16, 8), icosphere(1.0, 2),
+            cylinder(0.5, 2.0, 12, 2), cone(0.5, 1.0, 12, 1), capsule(0.5, 1.0, 12, 4), torus(1.0, 0.25, 16, 8),
+        )
+    }
+
+    #[test]
+    fn faces_wind_towards_their_normals() {
+        for object in all() {
+            let point = |i: usize| {
+                let v = object.vertices[i];
+                Vector3::new(v.x as f32, v.y as f32, v.z as f32)
+            };
+            for shape in object.geometry.iter().flat_map(|g| g.shapes.iter()) {
+                if let obj::Primitive::Triangle(a, b, c) = shape.primitive {
+                    let face = (point(b.0) - point(a.0)).cross(point(c.0) - point(a.0));
+                    let n = object.normals[a.2.unwrap()];
+                    let normal = Vector3::new(n.x as f32, n.y as f32, n.z as f32);
+                    assert!(face.dot(normal) > 0.0, "{} at {:?}", object.name, point(a.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_meshes_upload_like_files() {
+        for object in all() {
+            let data = MeshData::from_object(&object, &[], &NormalOptions::default()).unwrap();
+            assert_eq!(data.vertices.format(), VertexFormat::PositionNormalUvTangent, "{}", object.name);
+            assert!(data.triangle_count() > 0);
+        }
+
+        let cube = MeshData::from_object(&cube(2.0, 1), &[], &NormalOptions::default()).unwrap();
+        // four corners per face, shared corners differ in normal
+        assert_eq!((cube.vertices.len(), cube.triangle_count()), (24, 12));
+        assert_eq!(cube.bounds.max, Vector3::new(1.0, 1.0, 1.0));
+
+        let sphere = MeshData::from_object(&icosphere(2.0, 1), &[], &NormalOptions::default()).unwrap();
+        assert_eq!(sphere.triangle_count(), 80);
+        assert!((sphere.bounds.max.y - 2.0).abs() < 1.0e-5);
+
+        let board = checkerboard(8.0, 8);
+        assert_eq!(board.geometry[0].shapes.len(), 64);
+        assert_eq!(board.geometry[1].shapes.len(), 64);
+        match board.geometry[1].shapes[0].primitive {
+            obj::Primitive::Triangle(a, _, _) => assert_eq!(board.vertices[a.0].x, -4.0),
+            _ => panic!("not a triangle"),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::ops;
+
+use crate::game_engine::basis::Basis;
+use crate::game_engine::vector3::Vector3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quaternion {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Quaternion {
+        let axis = axis.normalized();
+        let (s, c) = (angle / 2.0).sin_cos();
+        Quaternion::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    // euler angles in radians, YXZ order, same as Basis
+    pub fn from_euler(euler: Vector3) -> Quaternion {
+        Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), euler.y)
+            * Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), euler.x)
+            * Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), euler.z)
+    }
+
+    // scale of the basis is ignored
+    pub fn from_basis(basis: &Basis) -> Quaternion {
+        let m = basis.get_rotation_basis();
+        let m = m.get_elements();
+        let trace = m[0].x + m[1].y + m[2].z;
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new((m[2].y - m[1].z) / s, (m[0].z - m[2].x) / s, (m[1].x - m[0].y) / s, s / 4.0)
+        } else if m[0].x > m[1].y && m[0].x > m[2].z {
+            let s = (1.0 + m[0].x - m[1].y - m[2].z).sqrt() * 2.0;
+            Quaternion::new(s / 4.0, (m[0].y + m[1].x) / s, (m[0].z + m[2].x) / s, (m[2].y - m[1].z) / s)
+        } else if m[1].y > m[2].z {
+            let s = (1.0 + m[1].y - m[0].x - m[2].z).sqrt() * 2.0;
+            Quaternion::new((m[0].y + m[1].x) / s, s / 4.0, (m[1].z + m[2].y) / s, (m[0].z - m[2].x) / s)
+        } else {
+            let s = (1.0 + m[2].z - m[0].x - m[1].y).sqrt() * 2.0;
+            Quaternion::new((m[0].z + m[2].x) / s, (m[1].z + m[2].y) / s, s / 4.0, (m[1].x - m[0].y) / s)
+        };
+        q.normalized()
+    }
+
+    pub fn to_basis(self) -> Basis {
+        let Quaternion { x, y, z, w } = self;
+        Basis::from_rows(
+            Vector3::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)),
+            Vector3::new(2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)),
+            Vector3::new(2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)),
+        )
+    }
+
+    pub fn get_euler(&self) -> Vector3 {
+        self.to_basis().get_rotation()
+    }
+
+    // returns normalized axis and angle in radians
+    pub fn get_axis_angle(&self) -> (Vector3, f32) {
+        let q = if self.w < 0.0 { -*self } else { *self };
+        let w = q.w.clamp(-1.0, 1.0);
+        let s = (1.0 - w * w).sqrt();
+        if s < 1.0e-6 {
+            return (Vector3::new(0.0, 1.0, 0.0), 0.0);
+        }
+        (Vector3::new(q.x / s, q.y / s, q.z / s), 2.0 * w.acos())
+    }
+
+    pub fn dot(&self, other: Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        *self = self.normalized();
+    }
+
+    pub fn normalized(&self) -> Quaternion {
+        *self * (1.0 / self.length())
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(&self) -> Quaternion {
+        self.conjugate() * (1.0 / self.dot(*self))
+    }
+
+    pub fn xform(&self, vector: Vector3) -> Vector3 {
+        let u = Vector3::new(self.x, self.y, self.z);
+        let t = u.cross(vector) * 2.0;
+        vector + t * self.w + u.cross(t)
+    }
+
+    // normalized linear interpolation along the shortest path
+    pub fn nlerp(&self, to: Quaternion, t: f32) -> Quaternion {
+        let to = if self.dot(to) < 0.0 { -to } else { to };
+        (*self * (1.0 - t) + to * t).normalized()
+    }
+
+    // spherical interpolation along the shortest path
+    pub fn slerp(&self, to: Quaternion, t: f32) -> Quaternion {
+        let mut cos = self.dot(to);
+        let to = if cos < 0.0 {
+            cos = -cos;
+            -to
+        } else {
+            to
+        };
+
+        if cos > 1.0 - 1.0e-5 {
+            return self.nlerp(to, t);
+        }
+
+        let omega = cos.acos();
+        let sin = omega.sin();
+        let scale_from = ((1.0 - t) * omega).sin() / sin;
+        let scale_to = (t * omega).sin() / sin;
+        *self * scale_from + to * scale_to
+    }
+}
+
+
+impl ops::Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y + self.y * rhs.w + self.z * rhs.x - self.x * rhs.z,
+            self.w * rhs.z + self.z * rhs.w + self.x * rhs.y - self.y * rhs.x,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+} // Quaternion * Quaternion
+
+impl ops::MulAssign<Quaternion> for Quaternion {
+    fn mul_assign(&mut self, rhs: Quaternion) {
+        *self = *self * rhs;
+    }
+} // Quaternion *= Quaternion
+
+impl ops::Mul<Vector3> for Quaternion {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        self.xform(rhs)
+    }
+} // Quaternion * Vector3
+
+impl ops::Mul<f32> for Quaternion {
+    type Output = Quaternion;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Quaternion::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
+    }
+} // Quaternion * f32
+
+impl ops::Add<Quaternion> for Quaternion {
+    type Output = Quaternion;
+    fn add(self, rhs: Quaternion) -> Self::Output {
+        Quaternion::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w + rhs.w)
+    }
+} // Quaternion + Quaternion
+
+impl ops::Neg for Quaternion {
+    type Output = Quaternion;
+    fn neg(self) -> Self::Output {
+        Quaternion::new(-self.x, -self.y, -self.z, -self.w)
+    }
+} // -Quaternion
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).length() < 1.0e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn xform_matches_basis() {
+        let q = Quaternion::from_axis_angle(Vector3::new(0.2, 1.0, -0.4), 1.3);
+        let v = Vector3::new(1.0, -2.0, 0.5);
+        assert_near(q.xform(v), q.to_basis().xform(v));
+    }
+
+    #[test]
+    fn basis_round_trip() {
+        let euler = Vector3::new(0.6, -2.5, 1.1);
+        let q = Quaternion::from_basis(&Basis::from_euler(euler));
+        assert!((q.dot(Quaternion::from_euler(euler)).abs() - 1.0).abs() < 1.0e-5);
+        assert_near(q.get_euler(), euler);
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let axis = Vector3::new(-1.0, 0.5, 2.0).normalized();
+        let (result_axis, angle) = Quaternion::from_axis_angle(axis, 2.2).get_axis_angle();
+        assert_near(result_axis, axis);
+        assert!((angle - 2.2).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn slerp_halfway() {
+        let from = Quaternion::identity();
+        let to = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+        let half = from.slerp(to, 0.5);
+        let expected = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2 / 2.0);
+        assert!((half.dot(expected) - 1.0).abs() < 1.0e-5);
+        assert!((from.nlerp(to, 0.5).dot(expected) - 1.0).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn multiplication_composes_rotations() {
+        let a = Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), 0.7);
+        let b = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), -1.1);
+        let v = Vector3::new(0.3, 0.4, 0.5);
+        assert_near((a * b).xform(v), a.xform(b.xform(v)));
+        assert_near((a * a.inverse()).xform(v), v);
+    }
+}
@@ -1,15 +1,30 @@
 use glium::{Display, Frame, Surface};
+use glium::uniforms::{AsUniformValue, UniformValue, Uniforms};
 
-use crate::game_engine::mesh::Mesh;
-use crate::game_engine::transform::Transform;
+use crate::game_engine::error::EngineError;
+use crate::game_engine::mesh::GpuMesh;
+use crate::game_engine::object3d::Object3D;
+use crate::game_engine::material::Material;
 use crate::game_engine::color::Color;
+use crate::game_engine::light::Light;
+use crate::game_engine::matrix4::Matrix4;
+
+// lights the shaders have room for, Scene::load rejects scenes with more
+pub const MAX_LIGHTS: usize = 4;
+const LIGHT_NAMES: [(&str, &str); MAX_LIGHTS] = [
+    ("u_lights[0]", "u_light_colors[0]"),
+    ("u_lights[1]", "u_light_colors[1]"),
+    ("u_lights[2]", "u_light_colors[2]"),
+    ("u_lights[3]", "u_light_colors[3]"),
+];
 
 pub struct Renderer<'a, 'b> {
     pub display: &'b Display,
     pub camera_position: [f32; 3],
-    pub light_position: [f32; 3],
-    pub view_matrix: [[f32; 4]; 4],
-    pub perspective_matrix: [[f32; 4]; 4],
+    // lights past MAX_LIGHTS are ignored
+    pub lights: Vec<Light>,
+    pub view_matrix: Matrix4,
+    pub perspective_matrix: Matrix4,
     pub params: glium::DrawParameters<'a>,
     target: Frame,
 }
@@ -17,9 +32,9 @@ pub struct Renderer<'a, 'b> {
 impl<'a, 'b> Renderer<'a, 'b> {
     pub fn new(d: &'b Display,
         cp: [f32; 3],
-        lp: [f32; 3],
-        v: [[f32; 4]; 4],
-        p: [[f32; 4]; 4],
+        lights: &[Light],
+        v: Matrix4,
+        p: Matrix4,
         params: glium::DrawParameters<'a>
     ) -> Renderer<'a, 'b> {
 
@@ -27,7 +42,7 @@ impl<'a, 'b> Renderer<'a, 'b> {
         Renderer {
             display: d,
             camera_position: cp,
-            light_position: lp,
+            lights: lights.to_vec(),
             view_matrix: v,
             perspective_matrix: p,
             params,
@@ -39,24 +54,48 @@ impl<'a, 'b> Renderer<'a, 'b> {
         self.target.clear_color_and_depth((color.r, color.g, color.b, color.a), 1.0);
     }
 
-    pub fn show(&mut self) {
-        self.target.set_finish();
+    pub fn show(&mut self) -> Result<(), EngineError> {
+        self.target.set_finish()?;
+        Ok(())
+    }
+
+    // draws a single object with its global transform, children are not drawn
+    pub fn draw(&mut self, object: &Object3D) -> Result<(), EngineError> {
+        match &object.mesh {
+            Some(mesh) => self.draw_mesh(mesh, &object.materials, object.get_global_transform().form_matrix()),
+            None => Ok(()),
+        }
+    }
+
+    // draws every mesh in the tree, global transforms must be up to date
+    pub fn draw_scene(&mut self, root: &Object3D) -> Result<(), EngineError> {
+        for object in root.iter() {
+            self.draw(object)?;
+        }
+        Ok(())
     }
 
-    pub fn draw(&mut self, mesh: &Mesh) {
-        for surface_i in 0..mesh.index_buffers.len() {
-            if mesh.materials.len() <= surface_i {
-                break;
+    fn draw_mesh(&mut self, mesh: &GpuMesh, materials: &[Material], model: Matrix4) -> Result<(), EngineError> {
+        for surface in &mesh.surfaces {
+            let material = match materials.get(surface.material) {
+                Some(material) => material,
+                None => continue,
+            };
+            let uniforms = DrawUniforms {
+                model,
+                view: self.view_matrix,
+                perspective: self.perspective_matrix,
+                lights: &self.lights,
+                material,
+            };
+            match &mesh.color_buffer {
+                Some(colors) => self.target.draw((&mesh.vertex_buffer, colors), &surface.indices, &material.shader,
+                    &uniforms, &self.params)?,
+                None => self.target.draw(&mesh.vertex_buffer, &surface.indices, &material.shader,
+                    &uniforms, &self.params)?,
             }
-            let material = &mesh.materials[surface_i];
-            self.target.draw(&mesh.vertex_buffer, &mesh.index_buffers[surface_i], &material.shader,
-                &uniform! { model: mesh.transform.form_matrix(),
-                    view: self.view_matrix,
-                    perspective: self.perspective_matrix,
-                    u_light: self.light_position,
-                    u_color: material.albedo.as_array_rgb() },
-                &self.params).unwrap();
         }
+        Ok(())
     }
 
     pub fn size(&self) -> (u32, u32) {
@@ -64,8 +103,36 @@ impl<'a, 'b> Renderer<'a, 'b> {
     }
 }
 
-impl Drop for Renderer<'_, '_> {
-    fn drop(&mut self) {
-        std::mem::drop(&self.target);
+// uniforms of one draw call, material maps are only passed when they are set
+struct DrawUniforms<'m> {
+    model: Matrix4,
+    view: Matrix4,
+    perspective: Matrix4,
+    lights: &'m [Light],
+    material: &'m Material,
+}
+
+impl Uniforms for DrawUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut output: F) {
+        output("model", self.model.as_uniform_value());
+        output("view", self.view.as_uniform_value());
+        output("perspective", self.perspective.as_uniform_value());
+        let lights = &self.lights[..self.lights.len().min(MAX_LIGHTS)];
+        output("u_light_count", UniformValue::SignedInt(lights.len() as i32));
+        for (light, (position, color)) in lights.iter().zip(LIGHT_NAMES.iter()) {
+            output(position, UniformValue::Vec3(light.position.as_array()));
+            output(color, UniformValue::Vec3(light.color.as_array_rgb()));
+        }
+        output("u_color", UniformValue::Vec3(self.material.albedo.as_array_rgb()));
+        output("u_ambient", UniformValue::Vec3(self.material.ambient.as_array_rgb()));
+        output("u_specular", UniformValue::Vec3(self.material.specular.as_array_rgb()));
+        output("u_shininess", UniformValue::Float(self.material.shininess));
+        output("u_emissive", UniformValue::Vec3(self.material.emissive.as_array_rgb()));
+        for (name, flag, slot) in self.material.texture_slots().iter() {
+            output(flag, UniformValue::Bool(slot.is_some()));
+            if let Some(slot) = slot {
+                output(name, slot.as_uniform_value());
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,607 @@
+use serde::{Deserialize, Serialize};
+use wavefront_obj::obj;
+
+use std::fs;
+
+use crate::game_engine::asset_manager::{self, AssetManager, Handle};
+use crate::game_engine::camera::Camera;
+use crate::game_engine::error::EngineError;
+use crate::game_engine::color::Color;
+use crate::game_engine::light::Light;
+use crate::game_engine::material::Material;
+use crate::game_engine::mesh::{GpuMesh, MeshData};
+use crate::game_engine::normals::NormalOptions;
+use crate::game_engine::object3d::Object3D;
+use crate::game_engine::primitives;
+use crate::game_engine::renderer::MAX_LIGHTS;
+use crate::game_engine::texture::{Sampler, TextureSlot};
+use crate::game_engine::transform::Transform;
+use crate::game_engine::vector3::Vector3;
+
+pub struct Scene {
+    pub root: Object3D,
+    pub camera: Camera,
+    pub lights: Vec<Light>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            root: Object3D::new("scene"),
+            camera: Camera::new(),
+            lights: Vec::new(),
+        }
+    }
+
+    // the scene path and the paths inside the scene file are relative to the asset root
+    pub fn load(path: &str, assets: &mut AssetManager) -> Result<Scene, EngineError> {
+        let source = assets.read_to_string(path)?;
+        let description: SceneDescription = ron::de::from_str(&source)
+            .map_err(|e| EngineError::SceneParse { path: Some(assets.resolve(path)), message: e.to_string() })?;
+
+        let mut scene = Scene::new();
+        scene.camera = description.camera.build();
+        scene.lights = build_lights(&description.lights).map_err(|e| e.with_path(&assets.resolve(path)))?;
+
+        for node in &description.nodes {
+            let object = build_node(node, assets)?;
+            scene.root.add_child(object);
+        }
+        scene.update();
+
+        Ok(scene)
+    }
+
+    // resources are referenced by the paths they were loaded from through assets
+    pub fn save(&self, path: &str, assets: &AssetManager) -> Result<(), EngineError> {
+        let file = assets.resolve(path);
+        let source = self.to_ron(assets).map_err(|e| e.with_path(&file))?;
+        fs::write(&file, source).map_err(|e| EngineError::io(&file, e))
+    }
+
+    pub fn to_ron(&self, assets: &AssetManager) -> Result<String, EngineError> {
+        let description = SceneDescription {
+            camera: CameraDescription::from_camera(&self.camera),
+            lights: self.lights.iter().map(LightDescription::from_light).collect(),
+            nodes: self.root.get_children().iter().map(|o| describe_node(o, assets)).collect::<Result<_, _>>()?,
+        };
+        ron::ser::to_string_pretty(&description, ron::ser::PrettyConfig::new())
+            .map_err(|e| EngineError::SceneSerialize { path: None, message: e.to_string() })
+    }
+
+    // recomputes the cached global transforms of the whole tree
+    pub fn update(&mut self) {
+        self.root.update_global_transforms();
+    }
+}
+
+// every file a scene file uses, relative to the asset root. reads only the scene file
+// so references can be checked without a display
+pub fn scene_references(source: &str) -> Result<Vec<String>, EngineError> {
+    let description: SceneDescription = ron::de::from_str(source)
+        .map_err(|e| EngineError::SceneParse { path: None, message: e.to_string() })?;
+    let mut references = Vec::new();
+    let mut nodes = description.nodes.iter().collect::<Vec<_>>();
+    while let Some(node) = nodes.pop() {
+        references.extend(node.mesh.iter().chain(node.model.iter()).cloned());
+        for material in &node.materials {
+            references.push(material.shader.vertex.clone());
+            references.push(material.shader.fragment.clone());
+            let maps = [&material.albedo_map, &material.normal_map, &material.roughness_metallic_map, &material.emissive_map];
+            references.extend(maps.iter().filter_map(|map| map.as_ref()).map(|map| map.path.clone()));
+        }
+        nodes.extend(node.children.iter());
+    }
+    // "file#name" meshes and gltf images are inside file
+    let mut files = Vec::<String>::new();
+    for reference in references {
+        let file = reference.split('#').next().unwrap_or("").to_string();
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// the shaders take at most MAX_LIGHTS lights, more are rejected instead of silently dropped
+fn build_lights(descriptions: &[LightDescription]) -> Result<Vec<Light>, EngineError> {
+    if descriptions.len() > MAX_LIGHTS {
+        let message = format!("{} lights, at most {} are supported", descriptions.len(), MAX_LIGHTS);
+        return Err(EngineError::SceneParse { path: None, message });
+    }
+    Ok(descriptions.iter().map(|l| l.build()).collect())
+}
+
+// every resource must have been loaded through assets, otherwise the scene can't refer to it
+fn describe_node(object: &Object3D, assets: &AssetManager) -> Result<NodeDescription, EngineError> {
+    let untracked = |what: &str| EngineError::SceneSerialize {
+        path: None,
+        message: format!("object '{}' uses a {} that wasn't loaded from a file", object.name, what),
+    };
+    let (mesh, primitive) = match &object.mesh {
+        Some(mesh) => {
+            let key = assets.get_mesh_path(mesh).ok_or_else(|| untracked("mesh"))?;
+            match PrimitiveDescription::from_key(key) {
+                Some(primitive) => (None, Some(primitive)),
+                None => (Some(key.to_string()), None),
+            }
+        },
+        None => (None, None),
+    };
+    let describe_map = |slot: &Option<TextureSlot>| -> Result<Option<TextureDescription>, EngineError> {
+        let slot = match slot {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+        let path = assets.get_texture_path(&slot.texture).ok_or_else(|| untracked("texture"))?;
+        Ok(Some(TextureDescription { path: path.to_string(), sampler: slot.sampler }))
+    };
+    let mut materials = Vec::new();
+    for material in &object.materials {
+        let (vertex, fragment) = assets.get_shader_paths(&material.shader).ok_or_else(|| untracked("shader"))?;
+        materials.push(MaterialDescription {
+            albedo: (material.albedo.r, material.albedo.g, material.albedo.b, material.albedo.a),
+            ambient: (material.ambient.r, material.ambient.g, material.ambient.b),
+            specular: (material.specular.r, material.specular.g, material.specular.b),
+            shininess: material.shininess,
+            shader: ShaderDescription { vertex: vertex.to_string(), fragment: fragment.to_string() },
+            emissive: Some((material.emissive.r, material.emissive.g, material.emissive.b)),
+            albedo_map: describe_map(&material.albedo_map)?,
+            normal_map: describe_map(&material.normal_map)?,
+            roughness_metallic_map: describe_map(&material.roughness_metallic_map)?,
+            emissive_map: describe_map(&material.emissive_map)?,
+        });
+    }
+
+    Ok(NodeDescription {
+        name: object.name.clone(),
+        transform: TransformDescription::from_transform(object.get_transform()),
+        mesh,
+        primitive,
+        // imported objects are saved as the children they became
+        model: None,
+        materials,
+        children: object.get_children().iter().map(|o| describe_node(o, assets)).collect::<Result<_, _>>()?,
+    })
+}
+
+fn build_node(node: &NodeDescription, assets: &mut AssetManager) -> Result<Object3D, EngineError> {
+    let mut object = Object3D::new(&node.name);
+    object.set_transform(node.transform.build());
+    let mesh = match (&node.mesh, &node.primitive) {
+        (Some(_), Some(_)) => {
+            let message = format!("node '{}' has both a mesh and a primitive", node.name);
+            return Err(EngineError::SceneParse { path: None, message });
+        },
+        (Some(path), None) => Some(assets.load_mesh(path)?),
+        (None, Some(primitive)) => Some(primitive.load(assets)?),
+        (None, None) => None,
+    };
+    if let Some(mesh) = mesh {
+        object.mesh = Some(assets.get_mesh(mesh));
+        // without materials in the scene file the mtl library of the mesh is used
+        if node.materials.is_empty() {
+            object.materials = assets.load_mesh_materials(mesh)?;
+        }
+    }
+    for description in &node.materials {
+        let shader = assets.load_shader(&description.shader.vertex, &description.shader.fragment)?;
+        let albedo = description.albedo;
+        let mut material = Material::new(Color::new(albedo.0, albedo.1, albedo.2, albedo.3), assets.get_shader(shader));
+        material.ambient = Color::new(description.ambient.0, description.ambient.1, description.ambient.2, 1.0);
+        material.specular = Color::new(description.specular.0, description.specular.1, description.specular.2, 1.0);
+        material.shininess = description.shininess;
+        material.albedo_map = build_map(&description.albedo_map, true, assets)?;
+        material.normal_map = build_map(&description.normal_map, false, assets)?;
+        material.roughness_metallic_map = build_map(&description.roughness_metallic_map, false, assets)?;
+        material.emissive = description.emissive_color();
+        material.emissive_map = build_map(&description.emissive_map, true, assets)?;
+        object.materials.push(material);
+    }
+    // a single material in the scene file is used for every surface of the mesh
+    if let (Some(mesh), [material]) = (&object.mesh, &object.materials[..]) {
+        object.materials = vec!(material.clone(); mesh.material_count.max(1));
+    }
+    if let Some(path) = &node.model {
+        for child in assets.load_model(path)? {
+            object.add_child(child);
+        }
+    }
+    for child in &node.children {
+        let child = build_node(child, assets)?;
+        object.add_child(child);
+    }
+    Ok(object)
+}
+
+fn build_map(description: &Option<TextureDescription>, srgb: bool, assets: &mut AssetManager)
+    -> Result<Option<TextureSlot>, EngineError> {
+    let description = match description {
+        Some(description) => description,
+        None => return Ok(None),
+    };
+    let texture = if srgb {
+        assets.load_texture(&description.path)?
+    } else {
+        assets.load_linear_texture(&description.path)?
+    };
+    Ok(Some(TextureSlot::new(assets.get_texture(texture), description.sampler)))
+}
+
+// file format, angles are in degrees
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SceneDescription {
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    #[serde(default)]
+    nodes: Vec<NodeDescription>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct NodeDescription {
+    name: String,
+    #[serde(default)]
+    transform: TransformDescription,
+    #[serde(default)]
+    mesh: Option<String>,
+    // a generated mesh instead of a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primitive: Option<PrimitiveDescription>,
+    // every root node of an obj or gltf file, added as children
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(default)]
+    materials: Vec<MaterialDescription>,
+    #[serde(default)]
+    children: Vec<NodeDescription>,
+}
+
+// the arguments of the generator in primitives with the same name
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+enum PrimitiveDescription {
+    Plane(f32, f32, usize, usize),
+    Checkerboard(f32, usize),
+    Cube(f32, usize),
+    UvSphere(f32, usize, usize),
+    Icosphere(f32, usize),
+    Cylinder(f32, f32, usize, usize),
+    Cone(f32, f32, usize, usize),
+    Capsule(f32, f32, usize, usize),
+    Torus(f32, f32, usize, usize),
+}
+
+const PRIMITIVE_PREFIX: &str = "primitive:";
+
+impl PrimitiveDescription {
+    fn build(&self) -> obj::Object {
+        match *self {
+            PrimitiveDescription::Plane(width, depth, columns, rows) => primitives::plane(width, depth, columns, rows),
+            PrimitiveDescription::Checkerboard(size, squares) => primitives::checkerboard(size, squares),
+            PrimitiveDescription::Cube(size, subdivisions) => primitives::cube(size, subdivisions),
+            PrimitiveDescription::UvSphere(radius, segments, rings) => primitives::uv_sphere(radius, segments, rings),
+            PrimitiveDescription::Icosphere(radius, subdivisions) => primitives::icosphere(radius, subdivisions),
+            PrimitiveDescription::Cylinder(radius, height, segments, rings) => primitives::cylinder(radius, height, segments, rings),
+            PrimitiveDescription::Cone(radius, height, segments, rings) => primitives::cone(radius, height, segments, rings),
+            PrimitiveDescription::Capsule(radius, height, segments, rings) => primitives::capsule(radius, height, segments, rings),
+            PrimitiveDescription::Torus(major, minor, segments, sides) => primitives::torus(major, minor, segments, sides),
+        }
+    }
+
+    // "primitive:" and the ron of the description, generated meshes are shared by nodes with the same description
+    fn key(&self) -> String {
+        format!("{}{}", PRIMITIVE_PREFIX, ron::ser::to_string(self).unwrap_or_default())
+    }
+
+    fn from_key(key: &str) -> Option<PrimitiveDescription> {
+        key.strip_prefix(PRIMITIVE_PREFIX).and_then(|description| ron::de::from_str(description).ok())
+    }
+
+    fn load(&self, assets: &mut AssetManager) -> Result<Handle<GpuMesh>, EngineError> {
+        let key = self.key();
+        match assets.find_mesh(&key) {
+            Some(mesh) => Ok(mesh),
+            None => assets.add_mesh(&key, &MeshData::from_object(&self.build(), &[], &NormalOptions::default())?),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+struct TransformDescription {
+    position: (f32, f32, f32),
+    rotation: (f32, f32, f32),
+    scale: (f32, f32, f32),
+}
+
+impl Default for TransformDescription {
+    fn default() -> Self {
+        TransformDescription {
+            position: (0.0, 0.0, 0.0),
+            rotation: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl TransformDescription {
+    fn from_transform(transform: &Transform) -> TransformDescription {
+        let rotation = transform.get_rotation();
+        TransformDescription {
+            position: transform.get_position().into(),
+            rotation: (rotation.x.to_degrees(), rotation.y.to_degrees(), rotation.z.to_degrees()),
+            scale: transform.get_scale().into(),
+        }
+    }
+
+    fn build(&self) -> Transform {
+        let rotation = Vector3::from(self.rotation);
+        let mut transform = Transform::new();
+        transform.scale(Vector3::from(self.scale));
+        transform.set_rotation(Vector3::new(rotation.x.to_radians(), rotation.y.to_radians(), rotation.z.to_radians()));
+        transform.set_position(Vector3::from(self.position));
+        transform
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct MaterialDescription {
+    albedo: (f32, f32, f32, f32),
+    #[serde(default = "white")]
+    ambient: (f32, f32, f32),
+    #[serde(default = "white")]
+    specular: (f32, f32, f32),
+    #[serde(default = "default_shininess")]
+    shininess: f32,
+    #[serde(default)]
+    shader: ShaderDescription,
+    // multiplies emissive_map, without one it is white with a map and black without
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    emissive: Option<(f32, f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    albedo_map: Option<TextureDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    normal_map: Option<TextureDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    roughness_metallic_map: Option<TextureDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    emissive_map: Option<TextureDescription>,
+}
+
+// the defaults of Material::new
+fn white() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+fn default_shininess() -> f32 {
+    16.0
+}
+
+impl MaterialDescription {
+    fn emissive_color(&self) -> Color {
+        let default = if self.emissive_map.is_some() { 1.0 } else { 0.0 };
+        let (r, g, b) = self.emissive.unwrap_or((default, default, default));
+        Color::new(r, g, b, 1.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct TextureDescription {
+    path: String,
+    #[serde(default)]
+    sampler: Sampler,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default)]
+struct ShaderDescription {
+    vertex: String,
+    fragment: String,
+}
+
+impl Default for ShaderDescription {
+    fn default() -> Self {
+        ShaderDescription {
+            vertex: asset_manager::DEFAULT_VERTEX_SHADER.to_string(),
+            fragment: asset_manager::DEFAULT_FRAGMENT_SHADER.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+struct CameraDescription {
+    transform: TransformDescription,
+    fov: f32,
+    znear: f32,
+    zfar: f32,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        CameraDescription::from_camera(&Camera::new())
+    }
+}
+
+impl CameraDescription {
+    fn from_camera(camera: &Camera) -> CameraDescription {
+        CameraDescription {
+            transform: TransformDescription::from_transform(&camera.transform),
+            fov: camera.fov.to_degrees(),
+            znear: camera.znear,
+            zfar: camera.zfar,
+        }
+    }
+
+    fn build(&self) -> Camera {
+        Camera {
+            transform: self.transform.build(),
+            fov: self.fov.to_radians(),
+            znear: self.znear,
+            zfar: self.zfar,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct LightDescription {
+    position: (f32, f32, f32),
+    #[serde(default = "white")]
+    color: (f32, f32, f32),
+}
+
+impl LightDescription {
+    fn from_light(light: &Light) -> LightDescription {
+        LightDescription {
+            position: light.position.into(),
+            color: (light.color.r, light.color.g, light.color.b),
+        }
+    }
+
+    fn build(&self) -> Light {
+        Light::new(Vector3::from(self.position), Color::new(self.color.0, self.color.1, self.color.2, 1.0))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess_scene_file_parses() {
+        let source = fs::read_to_string("assets/scenes/chess.ron").unwrap();
+        let description: SceneDescription = ron::de::from_str(&source).unwrap();
+        let board = &description.nodes[0];
+        assert_eq!(board.mesh.as_deref(), Some("models/board.obj"));
+        assert_eq!(board.children.iter().filter(|n| n.name.starts_with("pawn")).count(), 16);
+        assert_eq!(description.lights.len(), 1);
+        assert_eq!(board.materials.len(), 1);
+        let albedo_map = board.materials[0].albedo_map.as_ref().unwrap();
+        assert_eq!(albedo_map.path, "chess_texture.png");
+        assert!(!albedo_map.sampler.mipmaps);
+        assert_eq!(albedo_map.sampler.anisotropy, Sampler::default().anisotropy);
+    }
+
+    #[test]
+    fn references_of_chess_scene() {
+        let source = fs::read_to_string("assets/scenes/chess.ron").unwrap();
+        let references = scene_references(&source).unwrap();
+        assert!(references.contains(&"models/board.obj".to_string()));
+        assert!(references.contains(&"chess_texture.png".to_string()));
+        assert!(references.contains(&asset_manager::DEFAULT_VERTEX_SHADER.to_string()));
+        assert_eq!(references.iter().filter(|r| r.as_str() == "models/pawn.obj").count(), 1);
+        assert!(scene_references("(nodes: [(name: 1)])").is_err());
+    }
+
+    #[test]
+    fn description_round_trip() {
+        let source = r#"(
+            camera: (transform: (position: (0.0, 0.0, -2.0))),
+            lights: [(position: (1.4, 0.4, -0.7))],
+            nodes: [(
+                name: "board",
+                transform: (scale: (0.1, 0.1, 0.1), rotation: (0.0, 90.0, 0.0)),
+                mesh: Some("board.obj"),
+                materials: [(albedo: (1.0, 1.0, 1.0, 1.0))],
+                children: [(name: "rook"), (name: "set", model: Some("set.obj"))],
+            )],
+        )"#;
+        let description: SceneDescription = ron::de::from_str(source).unwrap();
+        let rook = &description.nodes[0].children[0];
+        assert_eq!(rook.transform, TransformDescription::default());
+        assert_eq!(rook.model, None);
+        assert_eq!(description.nodes[0].children[1].model.as_deref(), Some("set.obj"));
+        assert_eq!(description.nodes[0].materials[0].shader, ShaderDescription::default());
+
+        let saved = ron::ser::to_string_pretty(&description, ron::ser::PrettyConfig::new()).unwrap();
+        let reloaded: SceneDescription = ron::de::from_str(&saved).unwrap();
+        assert_eq!(reloaded, description);
+    }
+
+    #[test]
+    fn primitive_nodes_round_trip_through_their_mesh_key() {
+        let node: NodeDescription = ron::de::from_str(r#"(name: "squares", primitive: Some(Checkerboard(12.0, 8)))"#).unwrap();
+        let primitive = node.primitive.unwrap();
+        assert_eq!(node.mesh, None);
+        assert_eq!(PrimitiveDescription::from_key(&primitive.key()), Some(primitive.clone()));
+        assert_eq!(PrimitiveDescription::from_key("models/board.obj"), None);
+
+        let board = primitive.build();
+        assert_eq!(board.geometry.len(), 2);
+        let data = MeshData::from_object(&board, &[], &NormalOptions::default()).unwrap();
+        assert_eq!(data.material_count, 2);
+        assert_ne!(PrimitiveDescription::Cube(1.0, 1).key(), PrimitiveDescription::Cube(1.0, 2).key());
+    }
+
+    #[test]
+    fn material_description_keeps_lighting_fields() {
+        let plain: MaterialDescription = ron::de::from_str("(albedo: (1.0, 1.0, 1.0, 1.0))").unwrap();
+        assert_eq!((plain.ambient, plain.specular, plain.shininess), ((1.0, 1.0, 1.0), (1.0, 1.0, 1.0), 16.0));
+
+        let shiny: MaterialDescription = ron::de::from_str(
+            "(albedo: (1.0, 1.0, 1.0, 1.0), ambient: (0.2, 0.2, 0.2), specular: (0.5, 0.4, 0.3), shininess: 64.0)").unwrap();
+        let saved = ron::ser::to_string_pretty(&shiny, ron::ser::PrettyConfig::new()).unwrap();
+        let reloaded: MaterialDescription = ron::de::from_str(&saved).unwrap();
+        assert_eq!(reloaded, shiny);
+        assert_eq!(reloaded.specular, (0.5, 0.4, 0.3));
+        assert_eq!(reloaded.shininess, 64.0);
+    }
+
+    #[test]
+    fn emissive_map_alone_is_not_darkened() {
+        let only_map: MaterialDescription = ron::de::from_str(r#"(
+            albedo: (1.0, 1.0, 1.0, 1.0),
+            emissive_map: Some((path: "glow.png")),
+        )"#).unwrap();
+        assert_eq!(only_map.emissive_color(), Color::new(1.0, 1.0, 1.0, 1.0));
+
+        let tinted: MaterialDescription = ron::de::from_str(r#"(
+            albedo: (1.0, 1.0, 1.0, 1.0),
+            emissive: Some((0.5, 0.0, 0.0)),
+            emissive_map: Some((path: "glow.png")),
+        )"#).unwrap();
+        assert_eq!(tinted.emissive_color(), Color::new(0.5, 0.0, 0.0, 1.0));
+
+        let plain: MaterialDescription = ron::de::from_str("(albedo: (1.0, 1.0, 1.0, 1.0))").unwrap();
+        assert_eq!(plain.emissive_color(), Color::new(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn lights_keep_their_color_up_to_the_limit() {
+        let source = "[(position: (0.0, 1.0, 0.0), color: (1.0, 0.5, 0.0)), (position: (1.0, 0.0, 0.0))]";
+        let descriptions: Vec<LightDescription> = ron::de::from_str(source).unwrap();
+        let lights = build_lights(&descriptions).unwrap();
+        assert_eq!(lights[0].color, Color::new(1.0, 0.5, 0.0, 1.0));
+        assert_eq!(lights[1].color, Color::new(1.0, 1.0, 1.0, 1.0));
+
+        let too_many = (0..MAX_LIGHTS + 1).map(|_| LightDescription::from_light(&lights[0])).collect::<Vec<_>>();
+        assert!(build_lights(&too_many).is_err());
+    }
+
+    #[test]
+    fn transform_description_round_trip() {
+        let description = TransformDescription {
+            position: (1.0, -2.0, 3.0),
+            rotation: (10.0, 45.0, -30.0),
+            scale: (0.5, 0.5, 0.5),
+        };
+        let rebuilt = TransformDescription::from_transform(&description.build());
+        for (a, b) in [
+            (rebuilt.position, description.position),
+            (rebuilt.rotation, description.rotation),
+            (rebuilt.scale, description.scale),
+        ].iter() {
+            assert!((Vector3::from(*a) - Vector3::from(*b)).length() < 1.0e-3, "{:?} != {:?}", a, b);
+        }
+    }
+}
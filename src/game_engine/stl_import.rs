@@ -0,0 +1,92 @@
+use wavefront_obj::obj;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::game_engine::error::EngineError;
+
+// the object is named after the file
+pub fn import(file: &Path) -> Result<obj::Object, EngineError> {
+    let bytes = fs::read(file).map_err(|e| EngineError::io(file, e))?;
+    let name = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    parse(&bytes, &name).map_err(|e| e.with_path(file))
+}
+
+// binary or ascii stl. facet normals are often zero or inconsistent with the winding,
+// so they are dropped and normals are generated like for obj files without them
+pub fn parse(bytes: &[u8], name: &str) -> Result<obj::Object, EngineError> {
+    let mesh = stl_io::read_stl(&mut Cursor::new(bytes))
+        .map_err(|e| EngineError::ModelParse { path: None, message: e.to_string() })?;
+    let shapes = mesh.faces.iter().map(|face| {
+        let [a, b, c] = face.vertices;
+        obj::Shape {
+            primitive: obj::Primitive::Triangle((a, None, None), (b, None, None), (c, None, None)),
+            groups: Vec::new(),
+            smoothing_groups: Vec::new(),
+        }
+    }).collect();
+    Ok(obj::Object {
+        name: name.to_string(),
+        vertices: mesh.vertices.iter().map(|v| obj::Vertex { x: v.0[0] as f64, y: v.0[1] as f64, z: v.0[2] as f64 }).collect(),
+        tex_vertices: Vec::new(),
+        normals: Vec::new(),
+        geometry: vec!(obj::Geometry { material_name: None, shapes }),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_engine::mesh::MeshData;
+    use crate::game_engine::normals::NormalOptions;
+    use crate::game_engine::vector3::Vector3;
+
+    #[test]
+    fn ascii_and_binary_give_the_same_object() {
+        let ascii = "solid quad
+            facet normal 0 0 0
+                outer loop
+                    vertex 0 0 0
+                    vertex 1 0 0
+                    vertex 1 1 0
+                endloop
+            endfacet
+            facet normal 0 0 1
+                outer loop
+                    vertex 0 0 0
+                    vertex 1 1 0
+                    vertex 0 1 0
+                endloop
+            endfacet
+        endsolid quad";
+        let from_ascii = parse(ascii.as_bytes(), "quad").unwrap();
+
+        // 80 byte header, triangle count, then normal, three vertices and attribute bytes per triangle
+        let mut binary = vec![0u8; 80];
+        binary.extend_from_slice(&2u32.to_le_bytes());
+        let triangles = [[[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]], [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]];
+        for triangle in triangles.iter() {
+            for value in [0.0f32; 3].iter().chain(triangle.iter().flatten()) {
+                binary.extend_from_slice(&value.to_le_bytes());
+            }
+            binary.extend_from_slice(&[0, 0]);
+        }
+        let from_binary = parse(&binary, "quad").unwrap();
+
+        for object in [&from_ascii, &from_binary].iter() {
+            // shared corners are merged
+            assert_eq!(object.vertices.len(), 4);
+            assert_eq!(object.geometry[0].shapes.len(), 2);
+            assert!(object.normals.is_empty());
+        }
+        assert_eq!(from_ascii.geometry, from_binary.geometry);
+
+        // the triangles are coplanar, so even flat normals let them share corners
+        let flat = NormalOptions { smooth: false, ..NormalOptions::default() };
+        let data = MeshData::from_object(&from_binary, &[], &flat).unwrap();
+        assert_eq!((data.vertices.len(), data.triangle_count()), (4, 2));
+        assert_eq!(data.bounds.max, Vector3::new(1.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,147 @@
+use bevy_mikktspace::Geometry;
+use glium::index::PrimitiveType;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::game_engine::vertex_types::{VertexPNT, VertexPNTT};
+
+// tangent given to line and point vertices
+const DEFAULT_TANGENT: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+// triangles of an indexed mesh as seen by mikktspace, tangents are written per corner
+struct TangentInput<'a> {
+    vertices: &'a [VertexPNT],
+    triangles: Vec<[u32; 3]>,
+    tangents: Vec<[f32; 4]>,
+}
+
+impl Geometry for TangentInput<'_> {
+    fn num_faces(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        let p = self.vertices[self.triangles[face][vert] as usize].position;
+        [p.0, p.1, p.2]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        let n = self.vertices[self.triangles[face][vert] as usize].normal;
+        [n.0, n.1, n.2]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let uv = self.vertices[self.triangles[face][vert] as usize].uv;
+        [uv.0, uv.1]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent;
+    }
+}
+
+// result of generate_tangents, indices are per surface like the input
+pub struct TangentMesh {
+    pub vertices: Vec<VertexPNTT>,
+    // input vertex of every output vertex
+    pub sources: Vec<u32>,
+    pub indices: Vec<Vec<u32>>,
+}
+
+// mikktspace tangents for indexed surfaces, vertices whose corners get different
+// tangents are split. None if mikktspace rejects the geometry
+pub fn generate_tangents(vertices: &[VertexPNT], surfaces: &[(PrimitiveType, Vec<u32>)]) -> Option<TangentMesh> {
+
+    let triangles = surfaces.iter()
+        .filter(|(primitive, _)| *primitive == PrimitiveType::TrianglesList)
+        .flat_map(|(_, indices)| indices.chunks(3).map(|c| [c[0], c[1], c[2]]))
+        .collect::<Vec<_>>();
+    let mut input = TangentInput {
+        vertices,
+        tangents: vec![DEFAULT_TANGENT; triangles.len() * 3],
+        triangles,
+    };
+    if !input.triangles.is_empty() && !bevy_mikktspace::generate_tangents(&mut input) {
+        return None;
+    }
+
+    let mut result_vertices = Vec::<VertexPNTT>::new();
+    let mut sources = Vec::new();
+    let mut result_indices = Vec::new();
+    // keyed by input vertex and tangent, input vertices that only differ in
+    // attributes this function doesn't see (like colors) stay apart
+    let mut vertex_lookup = HashMap::<(u32, [u32; 4]), u32>::new();
+    let mut corner = 0;
+    for (primitive, indices) in surfaces {
+        let mut surface_indices = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let tangent = if *primitive == PrimitiveType::TrianglesList {
+                corner += 1;
+                input.tangents[corner - 1]
+            } else {
+                DEFAULT_TANGENT
+            };
+            let vertex = VertexPNTT::new(vertices[index as usize], tangent);
+            let bits = vertex.key();
+            let key = (index, [bits[8], bits[9], bits[10], bits[11]]);
+            let index = match vertex_lookup.get(&key) {
+                Some(&result_index) => result_index,
+                None => {
+                    let result_index = u32::try_from(result_vertices.len()).ok()?;
+                    result_vertices.push(vertex);
+                    sources.push(index);
+                    vertex_lookup.insert(key, result_index);
+                    result_index
+                }
+            };
+            surface_indices.push(index);
+        }
+        result_indices.push(surface_indices);
+    }
+    Some(TangentMesh { vertices: result_vertices, sources, indices: result_indices })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32) -> VertexPNT {
+        VertexPNT { position: (x, y, 0.0), normal: (0.0, 0.0, 1.0), uv: (x, y) }
+    }
+
+    #[test]
+    fn tangents_follow_u_direction() {
+        // a quad in the xy plane with uvs matching the positions
+        let vertices = vec!(vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(0.0, 1.0));
+        let surfaces = vec!((PrimitiveType::TrianglesList, vec!(0, 1, 2, 0, 2, 3)));
+        let TangentMesh { vertices: result, sources, indices } = generate_tangents(&vertices, &surfaces).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(sources, vec!(0, 1, 2, 3));
+        assert_eq!(indices, vec!(vec!(0, 1, 2, 0, 2, 3)));
+        for v in &result {
+            assert!((v.tangent.0 - 1.0).abs() < 1.0e-4 && v.tangent.1.abs() < 1.0e-4, "{:?}", v.tangent);
+            assert_eq!(v.tangent.3, 1.0);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_bitangent_sign() {
+        let mut vertices = vec!(vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0));
+        for v in vertices.iter_mut() {
+            v.uv.1 = -v.uv.1;
+        }
+        let surfaces = vec!((PrimitiveType::TrianglesList, vec!(0, 1, 2)), (PrimitiveType::LinesList, vec!(0, 1)));
+        let TangentMesh { vertices: result, sources, indices } = generate_tangents(&vertices, &surfaces).unwrap();
+        assert_eq!(result[0].tangent.3, -1.0);
+        assert_eq!(sources[indices[1][0] as usize], 0);
+        // line vertices keep the default tangent and are not shared with the triangle
+        assert_eq!(result[indices[1][0] as usize].tangent, (1.0, 0.0, 0.0, 1.0));
+        assert_eq!(result.len(), 5);
+    }
+}
@@ -0,0 +1,117 @@
+use glium::texture::{SrgbTexture2d, Texture2d};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, SamplerWrapFunction, UniformValue};
+use serde::{Deserialize, Serialize};
+
+use std::rc::Rc;
+
+// color maps are stored as srgb, data maps (normals, roughness) as linear
+pub enum Texture {
+    Srgb(SrgbTexture2d),
+    Linear(Texture2d),
+}
+
+impl Texture {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Texture::Srgb(texture) => texture.dimensions(),
+            Texture::Linear(texture) => texture.dimensions(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Wrap {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sampler {
+    pub wrap: Wrap,
+    pub filter: Filter,
+    // textures are always uploaded with mipmaps, this only decides if they are sampled
+    pub mipmaps: bool,
+    // 1 disables anisotropic filtering
+    pub anisotropy: u16,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Sampler {
+            wrap: Wrap::Repeat,
+            filter: Filter::Linear,
+            mipmaps: true,
+            anisotropy: 1,
+        }
+    }
+}
+
+impl Sampler {
+    pub fn to_behavior(self) -> SamplerBehavior {
+        let wrap = match self.wrap {
+            Wrap::Repeat => SamplerWrapFunction::Repeat,
+            Wrap::Mirror => SamplerWrapFunction::Mirror,
+            Wrap::Clamp => SamplerWrapFunction::Clamp,
+        };
+        let (minify_filter, magnify_filter) = match (self.filter, self.mipmaps) {
+            (Filter::Nearest, false) => (MinifySamplerFilter::Nearest, MagnifySamplerFilter::Nearest),
+            (Filter::Nearest, true) => (MinifySamplerFilter::NearestMipmapNearest, MagnifySamplerFilter::Nearest),
+            (Filter::Linear, false) => (MinifySamplerFilter::Linear, MagnifySamplerFilter::Linear),
+            (Filter::Linear, true) => (MinifySamplerFilter::LinearMipmapLinear, MagnifySamplerFilter::Linear),
+        };
+        SamplerBehavior {
+            wrap_function: (wrap, wrap, wrap),
+            minify_filter,
+            magnify_filter,
+            max_anisotropy: self.anisotropy.max(1),
+            ..Default::default()
+        }
+    }
+}
+
+// texture bound to one of the material map slots
+#[derive(Clone)]
+pub struct TextureSlot {
+    pub texture: Rc<Texture>,
+    pub sampler: Sampler,
+}
+
+impl TextureSlot {
+    pub fn new(texture: Rc<Texture>, sampler: Sampler) -> TextureSlot {
+        TextureSlot { texture, sampler }
+    }
+
+    pub fn as_uniform_value(&self) -> UniformValue<'_> {
+        let behavior = Some(self.sampler.to_behavior());
+        match self.texture.as_ref() {
+            Texture::Srgb(texture) => UniformValue::SrgbTexture2d(texture, behavior),
+            Texture::Linear(texture) => UniformValue::Texture2d(texture, behavior),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampler_filters() {
+        let behavior = Sampler { wrap: Wrap::Clamp, filter: Filter::Nearest, mipmaps: false, anisotropy: 0 }.to_behavior();
+        assert_eq!(behavior.wrap_function.0, SamplerWrapFunction::Clamp);
+        assert_eq!(behavior.minify_filter, MinifySamplerFilter::Nearest);
+        assert_eq!(behavior.max_anisotropy, 1);
+
+        let behavior = Sampler { anisotropy: 8, ..Default::default() }.to_behavior();
+        assert_eq!(behavior.minify_filter, MinifySamplerFilter::LinearMipmapLinear);
+        assert_eq!(behavior.max_anisotropy, 8);
+    }
+}
@@ -1,11 +1,22 @@
+use std::ops;
+
 use crate::game_engine::basis::Basis;
 use crate::game_engine::vector3::Vector3;
+use crate::game_engine::quaternion::Quaternion;
+use crate::game_engine::matrix4::Matrix4;
 
+#[derive(Copy, Clone, Debug)]
 pub struct Transform {
     basis: Basis,
     origin: Vector3,
 }
 
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::new()
+    }
+}
+
 impl Transform {
     pub fn new() -> Transform {
         Transform {
@@ -14,6 +25,10 @@ impl Transform {
         }
     }
 
+    pub fn from_basis_origin(basis: Basis, origin: Vector3) -> Transform {
+        Transform { basis, origin }
+    }
+
     pub fn get_position(&self) -> Vector3 {
         self.origin
     }
@@ -27,8 +42,69 @@ impl Transform {
         self.origin *= scale;
     }
 
+    pub fn get_basis(&self) -> &Basis {
+        &self.basis
+    }
+
+    pub fn set_basis(&mut self, basis: Basis) {
+        self.basis = basis;
+    }
+
+    // rotates around the parent origin, the position is rotated too
     pub fn rotate(&mut self, axis: Vector3, angle: f32) {
-        
+        let rotation = Basis::from_axis_angle(axis.normalized(), angle);
+        self.basis = rotation * self.basis;
+        self.origin = rotation.xform(self.origin);
+    }
+
+    pub fn rotate_x(&mut self, angle: f32) {
+        self.rotate(Vector3::new(1.0, 0.0, 0.0), angle);
+    }
+
+    pub fn rotate_y(&mut self, angle: f32) {
+        self.rotate(Vector3::new(0.0, 1.0, 0.0), angle);
+    }
+
+    pub fn rotate_z(&mut self, angle: f32) {
+        self.rotate(Vector3::new(0.0, 0.0, 1.0), angle);
+    }
+
+    // euler angles in radians, YXZ order
+    pub fn set_rotation(&mut self, euler: Vector3) {
+        self.basis.set_rotation(euler);
+    }
+
+    pub fn get_rotation(&self) -> Vector3 {
+        self.basis.get_rotation()
+    }
+
+    pub fn set_rotation_axis_angle(&mut self, axis: Vector3, angle: f32) {
+        self.basis.set_rotation_axis_angle(axis, angle);
+    }
+
+    pub fn get_rotation_axis_angle(&self) -> (Vector3, f32) {
+        self.basis.get_rotation_axis_angle()
+    }
+
+    // keeps the current scale
+    pub fn set_quaternion(&mut self, rotation: Quaternion) {
+        self.basis.set_rotation_quaternion(rotation);
+    }
+
+    pub fn get_quaternion(&self) -> Quaternion {
+        self.basis.get_rotation_quaternion()
+    }
+
+    // rotates around the parent origin, the position is rotated too
+    pub fn rotate_quaternion(&mut self, rotation: Quaternion) {
+        let rotation = rotation.normalized().to_basis();
+        self.basis = rotation * self.basis;
+        self.origin = rotation.xform(self.origin);
+    }
+
+    // turns local +Z towards target, keeps position and scale
+    pub fn look_at(&mut self, target: Vector3, up: Vector3) {
+        self.basis.look_at(target - self.origin, up);
     }
 
     pub fn translate(&mut self, translation: Vector3) {
@@ -42,13 +118,194 @@ impl Transform {
         self.origin = position;
     }
 
-    pub fn form_matrix(&self) -> [[f32; 4]; 4] {
-        let basis_elements = self.basis.get_elements();
-        [
-            [basis_elements[0].x, basis_elements[0].y, basis_elements[0].z, 0.0],
-            [basis_elements[1].x, basis_elements[1].y, basis_elements[1].z, 0.0],
-            [basis_elements[2].x, basis_elements[2].y, basis_elements[2].z, 0.0],
-            [self.origin.x, self.origin.y, self.origin.z, 1.0],
-        ]
-    }
-}
\ No newline at end of file
+    // copy of the transform with local +Z turned towards target
+    pub fn looking_at(&self, target: Vector3, up: Vector3) -> Transform {
+        let mut result = *self;
+        result.look_at(target, up);
+        result
+    }
+
+    // point from local space into parent space
+    pub fn xform(&self, point: Vector3) -> Vector3 {
+        self.basis.xform(point) + self.origin
+    }
+
+    // point from parent space into local space, None if the basis is singular
+    pub fn xform_inv(&self, point: Vector3) -> Option<Vector3> {
+        self.affine_inverse().map(|inverse| inverse.xform(point))
+    }
+
+    // direction from local space into parent space, ignores the origin
+    pub fn basis_xform(&self, direction: Vector3) -> Vector3 {
+        self.basis.xform(direction)
+    }
+
+    pub fn basis_xform_inv(&self, direction: Vector3) -> Option<Vector3> {
+        self.affine_inverse().map(|inverse| inverse.basis_xform(direction))
+    }
+
+    // only valid for rotation and translation, use affine_inverse if the transform is scaled
+    pub fn inverse(&self) -> Transform {
+        let basis = self.basis.transposed();
+        Transform::from_basis_origin(basis, basis.xform(-self.origin))
+    }
+
+    // None if the basis is singular, for example scaled by zero on an axis
+    pub fn affine_inverse(&self) -> Option<Transform> {
+        let basis = self.basis.inverse()?;
+        Some(Transform::from_basis_origin(basis, basis.xform(-self.origin)))
+    }
+
+    // interpolates position, rotation and scale separately
+    pub fn interpolate_with(&self, other: &Transform, weight: f32) -> Transform {
+        let rotation = self.get_quaternion().slerp(other.get_quaternion(), weight);
+        let mut basis = Basis::new();
+        basis.scale(self.get_scale().lerp(other.get_scale(), weight));
+        basis.set_rotation_quaternion(rotation);
+        Transform::from_basis_origin(basis, self.origin.lerp(other.origin, weight))
+    }
+
+    pub fn form_matrix(&self) -> Matrix4 {
+        Matrix4::from_basis_origin(&self.basis, self.origin)
+    }
+}
+
+
+impl ops::Mul<Transform> for Transform {
+    type Output = Transform;
+    fn mul(self, rhs: Transform) -> Self::Output {
+        Transform::from_basis_origin(self.basis * rhs.basis, self.xform(rhs.origin))
+    }
+} // Transform * Transform
+
+impl ops::MulAssign<Transform> for Transform {
+    fn mul_assign(&mut self, rhs: Transform) {
+        *self = *self * rhs;
+    }
+} // Transform *= Transform
+
+impl ops::Mul<Vector3> for Transform {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        self.xform(rhs)
+    }
+} // Transform * Vector3
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).length() < 1.0e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn form_matrix_applies_rotation_then_translation() {
+        let mut transform = Transform::new();
+        transform.rotate_z(FRAC_PI_2);
+        transform.set_position(Vector3::new(1.0, 2.0, 3.0));
+        let matrix = transform.form_matrix();
+        assert_near(matrix.transform_point(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(1.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn rotate_moves_origin() {
+        let mut transform = Transform::new();
+        transform.set_position(Vector3::new(0.0, 0.0, 1.0));
+        transform.rotate_y(FRAC_PI_2);
+        assert_near(transform.get_position(), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rotation_round_trips_through_form_matrix() {
+        let euler = Vector3::new(-0.4, 2.1, 0.25);
+        let mut transform = Transform::new();
+        transform.scale(Vector3::fill(0.5));
+        transform.set_rotation(euler);
+        assert_near(transform.get_rotation(), euler);
+
+        let matrix = transform.form_matrix();
+        let mut expected = Basis::from_euler(euler);
+        expected.scale(Vector3::fill(0.5));
+        let point = Vector3::new(0.3, -1.0, 2.0);
+        assert_near(matrix.transform_point(point), expected.xform(point));
+    }
+
+    #[test]
+    fn quaternion_round_trip_keeps_scale() {
+        let rotation = Quaternion::from_euler(Vector3::new(0.1, 0.9, -0.3));
+        let mut transform = Transform::new();
+        transform.scale(Vector3::new(1.0, 2.0, 3.0));
+        transform.set_quaternion(rotation);
+        assert!((transform.get_quaternion().dot(rotation).abs() - 1.0).abs() < 1.0e-5);
+        assert_near(transform.get_scale(), Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn look_at_faces_target() {
+        let mut transform = Transform::new();
+        transform.set_position(Vector3::new(1.0, 0.0, 1.0));
+        transform.look_at(Vector3::new(1.0, 0.0, 5.0), Vector3::new(0.0, 1.0, 0.0));
+        let matrix = transform.form_matrix();
+        assert_near(matrix.transform_point(Vector3::new(0.0, 0.0, 1.0)), Vector3::new(1.0, 0.0, 2.0));
+    }
+
+    fn sample_transform() -> Transform {
+        let mut transform = Transform::new();
+        transform.scale(Vector3::new(2.0, 0.5, 1.5));
+        transform.set_rotation(Vector3::new(0.3, -1.1, 0.6));
+        transform.set_position(Vector3::new(4.0, -2.0, 1.0));
+        transform
+    }
+
+    #[test]
+    fn composition_matches_matrices() {
+        let parent = sample_transform();
+        let mut child = Transform::new();
+        child.rotate_x(0.7);
+        child.set_position(Vector3::new(0.0, 1.0, -3.0));
+        let point = Vector3::new(0.5, 0.25, -1.0);
+        assert_near((parent * child).xform(point), parent.xform(child.xform(point)));
+        assert_near((parent * child).form_matrix().transform_point(point),
+            (parent.form_matrix() * child.form_matrix()).transform_point(point));
+    }
+
+    #[test]
+    fn inverses_undo_xform() {
+        let point = Vector3::new(-1.0, 3.0, 0.5);
+
+        let scaled = sample_transform();
+        assert_near(scaled.xform_inv(scaled.xform(point)).unwrap(), point);
+        assert_near((scaled.affine_inverse().unwrap() * scaled).xform(point), point);
+        assert_near(scaled.basis_xform_inv(scaled.basis_xform(point)).unwrap(), point);
+
+        let mut flat = sample_transform();
+        flat.scale(Vector3::new(1.0, 0.0, 1.0));
+        assert!(flat.affine_inverse().is_none());
+        assert!(flat.xform_inv(point).is_none() && flat.basis_xform_inv(point).is_none());
+
+        let mut rigid = Transform::new();
+        rigid.set_rotation(Vector3::new(1.0, 0.2, -0.4));
+        rigid.set_position(Vector3::new(1.0, 2.0, 3.0));
+        assert_near(rigid.inverse().xform(rigid.xform(point)), point);
+    }
+
+    #[test]
+    fn interpolate_with_blends_components() {
+        let from = Transform::new();
+        let mut to = Transform::new();
+        to.scale(Vector3::fill(3.0));
+        to.set_rotation(Vector3::new(0.0, 1.0, 0.0));
+        to.set_position(Vector3::new(2.0, 0.0, 0.0));
+
+        let half = from.interpolate_with(&to, 0.5);
+        assert_near(half.get_position(), Vector3::new(1.0, 0.0, 0.0));
+        assert_near(half.get_scale(), Vector3::fill(2.0));
+        assert_near(half.get_rotation(), Vector3::new(0.0, 0.5, 0.0));
+
+        let looking = from.looking_at(Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_near(looking.basis_xform(Vector3::new(0.0, 0.0, 1.0)), Vector3::new(0.0, 0.0, -1.0));
+    }
+}
@@ -0,0 +1,268 @@
+use std::ops;
+
+use crate::game_engine::math;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vector2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector2 {
+    pub fn new(x: f32, y: f32) -> Vector2 {
+        Vector2 { x, y }
+    }
+
+    pub fn fill(value: f32) -> Vector2 {
+        Vector2 { x: value, y: value }
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.x.powi(2) + self.y.powi(2)).sqrt()
+    }
+
+    pub fn dot(&self, other: Vector2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        *self /= self.magnitude();
+    }
+
+    pub fn normalized(&self) -> Vector2 {
+        *self / self.magnitude()
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn distance_to(&self, other: Vector2) -> f32 {
+        (other - *self).length()
+    }
+
+    pub fn distance_squared_to(&self, other: Vector2) -> f32 {
+        (other - *self).length_squared()
+    }
+
+    pub fn lerp(&self, to: Vector2, weight: f32) -> Vector2 {
+        *self + (to - *self) * weight
+    }
+
+    // normal is expected to be normalized
+    pub fn reflect(&self, normal: Vector2) -> Vector2 {
+        *self - normal * (2.0 * self.dot(normal))
+    }
+
+    pub fn project(&self, onto: Vector2) -> Vector2 {
+        onto * (self.dot(onto) / onto.length_squared())
+    }
+
+    pub fn min(&self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    pub fn max(&self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+
+    pub fn clamp(&self, min: Vector2, max: Vector2) -> Vector2 {
+        self.max(min).min(max)
+    }
+
+    // radians, always positive
+    pub fn angle_to(&self, other: Vector2) -> f32 {
+        (self.dot(other) / (self.length() * other.length())).clamp(-1.0, 1.0).acos()
+    }
+
+    pub fn is_equal_approx(&self, other: Vector2) -> bool {
+        math::is_equal_approx(self.x, other.x) && math::is_equal_approx(self.y, other.y)
+    }
+
+    pub fn as_array(self) -> [f32; 2] {
+        [self.x, self.y]
+    }
+}
+
+
+impl ops::Index<usize> for Vector2 {
+    type Output = f32;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => panic!("Vector2 index out of range: {}", index),
+        }
+    }
+} // Vector2[usize]
+
+impl ops::IndexMut<usize> for Vector2 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!("Vector2 index out of range: {}", index),
+        }
+    }
+} // Vector2[usize] = f32
+
+
+impl From<[f32; 2]> for Vector2 {
+    fn from(array: [f32; 2]) -> Self {
+        Vector2::new(array[0], array[1])
+    }
+}
+
+impl From<Vector2> for [f32; 2] {
+    fn from(vector: Vector2) -> Self {
+        vector.as_array()
+    }
+}
+
+impl From<(f32, f32)> for Vector2 {
+    fn from(tuple: (f32, f32)) -> Self {
+        Vector2::new(tuple.0, tuple.1)
+    }
+}
+
+impl From<Vector2> for (f32, f32) {
+    fn from(vector: Vector2) -> Self {
+        (vector.x, vector.y)
+    }
+}
+
+
+impl ops::Mul<Vector2> for Vector2 {
+    type Output = Vector2;
+    fn mul(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x * rhs.x, self.y * rhs.y)
+    }
+} // Vector2 * Vector2
+
+impl ops::Mul<f32> for Vector2 {
+    type Output = Vector2;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x * rhs, self.y * rhs)
+    }
+} // Vector2 * f32
+
+impl ops::MulAssign<Vector2> for Vector2 {
+    fn mul_assign(&mut self, rhs: Vector2) {
+        self.x *= rhs.x; self.y *= rhs.y;
+    }
+} // Vector2 *= Vector2
+
+impl ops::MulAssign<f32> for Vector2 {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.x *= rhs; self.y *= rhs;
+    }
+} // Vector2 *= f32
+
+
+impl ops::Div<Vector2> for Vector2 {
+    type Output = Vector2;
+    fn div(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x / rhs.x, self.y / rhs.y)
+    }
+} // Vector2 / Vector2
+
+impl ops::Div<f32> for Vector2 {
+    type Output = Vector2;
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x / rhs, self.y / rhs)
+    }
+} // Vector2 / f32
+
+impl ops::DivAssign<Vector2> for Vector2 {
+    fn div_assign(&mut self, rhs: Vector2) {
+        self.x /= rhs.x; self.y /= rhs.y;
+    }
+} // Vector2 /= Vector2
+
+impl ops::DivAssign<f32> for Vector2 {
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs; self.y /= rhs;
+    }
+} // Vector2 /= f32
+
+
+impl ops::Add<Vector2> for Vector2 {
+    type Output = Vector2;
+    fn add(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+} // Vector2 + Vector2
+
+impl ops::AddAssign<Vector2> for Vector2 {
+    fn add_assign(&mut self, rhs: Vector2) {
+        self.x += rhs.x; self.y += rhs.y;
+    }
+} // Vector2 += Vector2
+
+impl ops::Add<f32> for Vector2 {
+    type Output = Vector2;
+    fn add(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x + rhs, self.y + rhs)
+    }
+} // Vector2 + f32
+
+impl ops::AddAssign<f32> for Vector2 {
+    fn add_assign(&mut self, rhs: f32) {
+        self.x += rhs; self.y += rhs;
+    }
+} // Vector2 += f32
+
+
+impl ops::Sub<Vector2> for Vector2 {
+    type Output = Vector2;
+    fn sub(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+} // Vector2 - Vector2
+
+impl ops::SubAssign<Vector2> for Vector2 {
+    fn sub_assign(&mut self, rhs: Vector2) {
+        self.x -= rhs.x; self.y -= rhs.y;
+    }
+} // Vector2 -= Vector2
+
+impl ops::Sub<f32> for Vector2 {
+    type Output = Vector2;
+    fn sub(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x - rhs, self.y - rhs)
+    }
+} // Vector2 - f32
+
+impl ops::SubAssign<f32> for Vector2 {
+    fn sub_assign(&mut self, rhs: f32) {
+        self.x -= rhs; self.y -= rhs;
+    }
+} // Vector2 -= f32
+
+
+impl ops::Neg for Vector2 {
+    type Output = Vector2;
+    fn neg(self) -> Self::Output {
+        Vector2::new(-self.x, -self.y)
+    }
+} // -Vector2
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_and_conversions() {
+        let mut v = Vector2::from((3.0, 4.0));
+        assert_eq!(v.length(), 5.0);
+        v -= Vector2::fill(1.0);
+        assert_eq!(v * 2.0, Vector2::new(4.0, 6.0));
+        assert_eq!(<[f32; 2]>::from(v), [2.0, 3.0]);
+        assert!((Vector2::new(1.0, 0.0).angle_to(Vector2::new(0.0, 2.0)) - std::f32::consts::FRAC_PI_2).abs() < 1.0e-6);
+    }
+}
@@ -1,6 +1,8 @@
 use std::ops;
 
-#[derive(Copy, Clone, Debug)]
+use crate::game_engine::math;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Vector3 {
     pub x: f32,
     pub y: f32,
@@ -44,12 +46,109 @@ impl Vector3 {
         )
     }
 
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn distance_to(&self, other: Vector3) -> f32 {
+        (other - *self).length()
+    }
+
+    pub fn distance_squared_to(&self, other: Vector3) -> f32 {
+        (other - *self).length_squared()
+    }
+
+    pub fn lerp(&self, to: Vector3, weight: f32) -> Vector3 {
+        *self + (to - *self) * weight
+    }
+
+    // normal is expected to be normalized
+    pub fn reflect(&self, normal: Vector3) -> Vector3 {
+        *self - normal * (2.0 * self.dot(normal))
+    }
+
+    pub fn project(&self, onto: Vector3) -> Vector3 {
+        onto * (self.dot(onto) / onto.length_squared())
+    }
+
+    pub fn min(&self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+
+    pub fn max(&self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+
+    pub fn clamp(&self, min: Vector3, max: Vector3) -> Vector3 {
+        self.max(min).min(max)
+    }
+
+    // radians, always positive
+    pub fn angle_to(&self, other: Vector3) -> f32 {
+        (self.dot(other) / (self.length() * other.length())).clamp(-1.0, 1.0).acos()
+    }
+
+    pub fn is_equal_approx(&self, other: Vector3) -> bool {
+        math::is_equal_approx(self.x, other.x)
+            && math::is_equal_approx(self.y, other.y)
+            && math::is_equal_approx(self.z, other.z)
+    }
+
     pub fn as_array(self) -> [f32; 3] {
         [self.x, self.y, self.z]
     }
 }
 
 
+impl ops::Index<usize> for Vector3 {
+    type Output = f32;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector3 index out of range: {}", index),
+        }
+    }
+} // Vector3[usize]
+
+impl ops::IndexMut<usize> for Vector3 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vector3 index out of range: {}", index),
+        }
+    }
+} // Vector3[usize] = f32
+
+
+impl From<[f32; 3]> for Vector3 {
+    fn from(array: [f32; 3]) -> Self {
+        Vector3::new(array[0], array[1], array[2])
+    }
+}
+
+impl From<Vector3> for [f32; 3] {
+    fn from(vector: Vector3) -> Self {
+        vector.as_array()
+    }
+}
+
+impl From<(f32, f32, f32)> for Vector3 {
+    fn from(tuple: (f32, f32, f32)) -> Self {
+        Vector3::new(tuple.0, tuple.1, tuple.2)
+    }
+}
+
+impl From<Vector3> for (f32, f32, f32) {
+    fn from(vector: Vector3) -> Self {
+        (vector.x, vector.y, vector.z)
+    }
+}
+
+
 impl ops::Mul<Vector3> for Vector3 {
     type Output = Vector3;
     fn mul(self, rhs: Vector3) -> Self::Output {
@@ -163,4 +262,39 @@ impl ops::Neg for Vector3 {
     fn neg(self) -> Self::Output {
         Vector3::new(-self.x, -self.y, -self.z)
     }
-} // -Vector3
\ No newline at end of file
+} // -Vector3
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflect_and_project() {
+        let v = Vector3::new(1.0, -1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(v.reflect(up), Vector3::new(1.0, 1.0, 0.0));
+        assert_eq!(v.project(up * 2.0), Vector3::new(0.0, -1.0, 0.0));
+        assert!((v.angle_to(up) - 3.0 * std::f32::consts::FRAC_PI_4).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn min_max_clamp_lerp() {
+        let a = Vector3::new(-2.0, 0.5, 3.0);
+        let b = Vector3::new(1.0, 1.0, 1.0);
+        assert_eq!(a.min(b), Vector3::new(-2.0, 0.5, 1.0));
+        assert_eq!(a.max(b), Vector3::new(1.0, 1.0, 3.0));
+        assert_eq!(a.clamp(Vector3::fill(-1.0), Vector3::fill(1.0)), Vector3::new(-1.0, 0.5, 1.0));
+        assert!(a.lerp(b, 0.5).is_equal_approx(Vector3::new(-0.5, 0.75, 2.0)));
+        assert_eq!(a.distance_to(a + Vector3::new(0.0, 3.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn index_and_conversions() {
+        let mut v = Vector3::from([1.0, 2.0, 3.0]);
+        v[1] = 5.0;
+        assert_eq!(v[1], 5.0);
+        assert_eq!(<(f32, f32, f32)>::from(v), (1.0, 5.0, 3.0));
+        assert_eq!(Vector3::from((1.0, 5.0, 3.0)), v);
+        assert_eq!(Vector3::default(), Vector3::fill(0.0));
+    }
+}
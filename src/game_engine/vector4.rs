@@ -0,0 +1,270 @@
+use std::ops;
+
+use crate::game_engine::math;
+use crate::game_engine::vector3::Vector3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vector4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Vector4 {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vector4 {
+        Vector4 { x, y, z, w }
+    }
+
+    pub fn fill(value: f32) -> Vector4 {
+        Vector4 { x: value, y: value, z: value, w: value }
+    }
+
+    pub fn from_vector3(vector: Vector3, w: f32) -> Vector4 {
+        Vector4::new(vector.x, vector.y, vector.z, w)
+    }
+
+    pub fn xyz(&self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.x.powi(2) + self.y.powi(2) + self.z.powi(2) + self.w.powi(2)).sqrt()
+    }
+
+    pub fn dot(&self, other: Vector4) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        *self /= self.magnitude();
+    }
+
+    pub fn normalized(&self) -> Vector4 {
+        *self / self.magnitude()
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn distance_to(&self, other: Vector4) -> f32 {
+        (other - *self).length()
+    }
+
+    pub fn distance_squared_to(&self, other: Vector4) -> f32 {
+        (other - *self).length_squared()
+    }
+
+    pub fn lerp(&self, to: Vector4, weight: f32) -> Vector4 {
+        *self + (to - *self) * weight
+    }
+
+    // normal is expected to be normalized
+    pub fn reflect(&self, normal: Vector4) -> Vector4 {
+        *self - normal * (2.0 * self.dot(normal))
+    }
+
+    pub fn project(&self, onto: Vector4) -> Vector4 {
+        onto * (self.dot(onto) / onto.length_squared())
+    }
+
+    pub fn min(&self, other: Vector4) -> Vector4 {
+        Vector4::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z), self.w.min(other.w))
+    }
+
+    pub fn max(&self, other: Vector4) -> Vector4 {
+        Vector4::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z), self.w.max(other.w))
+    }
+
+    pub fn clamp(&self, min: Vector4, max: Vector4) -> Vector4 {
+        self.max(min).min(max)
+    }
+
+    // radians, always positive
+    pub fn angle_to(&self, other: Vector4) -> f32 {
+        (self.dot(other) / (self.length() * other.length())).clamp(-1.0, 1.0).acos()
+    }
+
+    pub fn is_equal_approx(&self, other: Vector4) -> bool {
+        math::is_equal_approx(self.x, other.x)
+            && math::is_equal_approx(self.y, other.y)
+            && math::is_equal_approx(self.z, other.z)
+            && math::is_equal_approx(self.w, other.w)
+    }
+
+    pub fn as_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+
+impl ops::Index<usize> for Vector4 {
+    type Output = f32;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("Vector4 index out of range: {}", index),
+        }
+    }
+} // Vector4[usize]
+
+impl ops::IndexMut<usize> for Vector4 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("Vector4 index out of range: {}", index),
+        }
+    }
+} // Vector4[usize] = f32
+
+
+impl From<[f32; 4]> for Vector4 {
+    fn from(array: [f32; 4]) -> Self {
+        Vector4::new(array[0], array[1], array[2], array[3])
+    }
+}
+
+impl From<Vector4> for [f32; 4] {
+    fn from(vector: Vector4) -> Self {
+        vector.as_array()
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for Vector4 {
+    fn from(tuple: (f32, f32, f32, f32)) -> Self {
+        Vector4::new(tuple.0, tuple.1, tuple.2, tuple.3)
+    }
+}
+
+impl From<Vector4> for (f32, f32, f32, f32) {
+    fn from(vector: Vector4) -> Self {
+        (vector.x, vector.y, vector.z, vector.w)
+    }
+}
+
+
+impl ops::Mul<Vector4> for Vector4 {
+    type Output = Vector4;
+    fn mul(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z, self.w * rhs.w)
+    }
+} // Vector4 * Vector4
+
+impl ops::Mul<f32> for Vector4 {
+    type Output = Vector4;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
+    }
+} // Vector4 * f32
+
+impl ops::MulAssign<Vector4> for Vector4 {
+    fn mul_assign(&mut self, rhs: Vector4) {
+        self.x *= rhs.x; self.y *= rhs.y; self.z *= rhs.z; self.w *= rhs.w;
+    }
+} // Vector4 *= Vector4
+
+impl ops::MulAssign<f32> for Vector4 {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.x *= rhs; self.y *= rhs; self.z *= rhs; self.w *= rhs;
+    }
+} // Vector4 *= f32
+
+
+impl ops::Div<Vector4> for Vector4 {
+    type Output = Vector4;
+    fn div(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z, self.w / rhs.w)
+    }
+} // Vector4 / Vector4
+
+impl ops::Div<f32> for Vector4 {
+    type Output = Vector4;
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x / rhs, self.y / rhs, self.z / rhs, self.w / rhs)
+    }
+} // Vector4 / f32
+
+impl ops::DivAssign<Vector4> for Vector4 {
+    fn div_assign(&mut self, rhs: Vector4) {
+        self.x /= rhs.x; self.y /= rhs.y; self.z /= rhs.z; self.w /= rhs.w;
+    }
+} // Vector4 /= Vector4
+
+impl ops::DivAssign<f32> for Vector4 {
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs; self.y /= rhs; self.z /= rhs; self.w /= rhs;
+    }
+} // Vector4 /= f32
+
+
+impl ops::Add<Vector4> for Vector4 {
+    type Output = Vector4;
+    fn add(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w + rhs.w)
+    }
+} // Vector4 + Vector4
+
+impl ops::AddAssign<Vector4> for Vector4 {
+    fn add_assign(&mut self, rhs: Vector4) {
+        self.x += rhs.x; self.y += rhs.y; self.z += rhs.z; self.w += rhs.w;
+    }
+} // Vector4 += Vector4
+
+impl ops::Add<f32> for Vector4 {
+    type Output = Vector4;
+    fn add(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x + rhs, self.y + rhs, self.z + rhs, self.w + rhs)
+    }
+} // Vector4 + f32
+
+impl ops::AddAssign<f32> for Vector4 {
+    fn add_assign(&mut self, rhs: f32) {
+        self.x += rhs; self.y += rhs; self.z += rhs; self.w += rhs;
+    }
+} // Vector4 += f32
+
+
+impl ops::Sub<Vector4> for Vector4 {
+    type Output = Vector4;
+    fn sub(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z, self.w - rhs.w)
+    }
+} // Vector4 - Vector4
+
+impl ops::SubAssign<Vector4> for Vector4 {
+    fn sub_assign(&mut self, rhs: Vector4) {
+        self.x -= rhs.x; self.y -= rhs.y; self.z -= rhs.z; self.w -= rhs.w;
+    }
+} // Vector4 -= Vector4
+
+impl ops::Sub<f32> for Vector4 {
+    type Output = Vector4;
+    fn sub(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x - rhs, self.y - rhs, self.z - rhs, self.w - rhs)
+    }
+} // Vector4 - f32
+
+impl ops::SubAssign<f32> for Vector4 {
+    fn sub_assign(&mut self, rhs: f32) {
+        self.x -= rhs; self.y -= rhs; self.z -= rhs; self.w -= rhs;
+    }
+} // Vector4 -= f32
+
+
+impl ops::Neg for Vector4 {
+    type Output = Vector4;
+    fn neg(self) -> Self::Output {
+        Vector4::new(-self.x, -self.y, -self.z, -self.w)
+    }
+} // -Vector4
@@ -1,6 +1,4 @@
-use wavefront_obj::obj;
-
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct VertexPN {
     pub position: (f32, f32, f32),
     pub normal: (f32, f32, f32),
@@ -8,21 +6,107 @@ pub struct VertexPN {
 
 implement_vertex!(VertexPN, position, normal);
 
-impl std::cmp::PartialEq<(obj::Vertex, obj::Vertex)> for VertexPN {
-    fn eq(&self, other: &(obj::Vertex, obj::Vertex)) -> bool {
-        self.position.0 == other.0.x as f32 &&
-        self.position.1 == other.0.y as f32 &&
-        self.position.2 == other.0.z as f32 &&
-        
-        self.normal.0 == other.1.x as f32 &&
-        self.normal.1 == other.1.y as f32 &&
-        self.normal.2 == other.1.z as f32
+impl std::cmp::PartialEq for VertexPN {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position &&
+        self.normal == other.normal
     }
 }
 
-impl std::cmp::PartialEq for VertexPN {
+#[derive(Copy, Clone, Debug)]
+pub struct VertexPNT {
+    pub position: (f32, f32, f32),
+    pub normal: (f32, f32, f32),
+    pub uv: (f32, f32),
+}
+
+implement_vertex!(VertexPNT, position, normal, uv);
+
+impl std::cmp::PartialEq for VertexPNT {
     fn eq(&self, other: &Self) -> bool {
         self.position == other.position &&
-        self.normal == other.normal
+        self.normal == other.normal &&
+        self.uv == other.uv
+    }
+}
+
+impl VertexPNT {
+    // bit pattern of the attributes for hashing, -0.0 is stored as 0.0 to match ==
+    pub fn key(&self) -> [u32; 8] {
+        let bits = |x: f32| if x == 0.0 { 0 } else { x.to_bits() };
+        [
+            bits(self.position.0), bits(self.position.1), bits(self.position.2),
+            bits(self.normal.0), bits(self.normal.1), bits(self.normal.2),
+            bits(self.uv.0), bits(self.uv.1),
+        ]
+    }
+}
+
+impl From<VertexPNT> for VertexPN {
+    fn from(vertex: VertexPNT) -> Self {
+        VertexPN { position: vertex.position, normal: vertex.normal }
+    }
+}
+
+// position, normal, uv and tangent, w of the tangent is the bitangent sign
+#[derive(Copy, Clone, Debug)]
+pub struct VertexPNTT {
+    pub position: (f32, f32, f32),
+    pub normal: (f32, f32, f32),
+    pub uv: (f32, f32),
+    pub tangent: (f32, f32, f32, f32),
+}
+
+implement_vertex!(VertexPNTT, position, normal, uv, tangent);
+
+impl std::cmp::PartialEq for VertexPNTT {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position &&
+        self.normal == other.normal &&
+        self.uv == other.uv &&
+        self.tangent == other.tangent
     }
-}
\ No newline at end of file
+}
+
+impl VertexPNTT {
+    pub fn new(vertex: VertexPNT, tangent: [f32; 4]) -> VertexPNTT {
+        VertexPNTT {
+            position: vertex.position,
+            normal: vertex.normal,
+            uv: vertex.uv,
+            tangent: (tangent[0], tangent[1], tangent[2], tangent[3]),
+        }
+    }
+
+    pub fn key(&self) -> [u32; 12] {
+        let bits = |x: f32| if x == 0.0 { 0 } else { x.to_bits() };
+        let base = VertexPNT { position: self.position, normal: self.normal, uv: self.uv }.key();
+        [
+            base[0], base[1], base[2], base[3], base[4], base[5], base[6], base[7],
+            bits(self.tangent.0), bits(self.tangent.1), bits(self.tangent.2), bits(self.tangent.3),
+        ]
+    }
+}
+
+// per-vertex color in a second buffer next to the vertices, linear rgba
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VertexColor {
+    pub color: (f32, f32, f32, f32),
+}
+
+implement_vertex!(VertexColor, color);
+
+impl VertexColor {
+    pub fn key(&self) -> [u32; 4] {
+        let bits = |x: f32| if x == 0.0 { 0 } else { x.to_bits() };
+        [bits(self.color.0), bits(self.color.1), bits(self.color.2), bits(self.color.3)]
+    }
+}
+
+// vertex type stored in a mesh vertex buffer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VertexFormat {
+    PositionNormal,
+    PositionNormalUv,
+    PositionNormalUvTangent,
+}
@@ -0,0 +1,5 @@
+#[macro_use]
+extern crate glium;
+extern crate wavefront_obj;
+
+pub mod game_engine;
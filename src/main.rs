@@ -1,23 +1,18 @@
-#[macro_use]
 extern crate glium;
-extern crate wavefront_obj;
 
-use glium::{glutin, Surface};
+use glium::glutin;
 use glutin::event;
-use wavefront_obj::obj;
 
-use std::fs;
-use std::rc::Rc;
+use std::path::PathBuf;
 
-mod game_engine;
-
-use game_engine::object3d::Object3D;
-use game_engine::math;
-use game_engine::vector3::Vector3;
-use game_engine::renderer::Renderer;
-use game_engine::mesh::Mesh;
-use game_engine::material::Material;
-use game_engine::color::Color;
+use game_engine_glium::game_engine::vector2::Vector2;
+use game_engine_glium::game_engine::vector3::Vector3;
+use game_engine_glium::game_engine::quaternion::Quaternion;
+use game_engine_glium::game_engine::renderer::Renderer;
+use game_engine_glium::game_engine::scene::Scene;
+use game_engine_glium::game_engine::asset_manager::AssetManager;
+use game_engine_glium::game_engine::color::Color;
+use game_engine_glium::game_engine::light::Light;
 
 fn main() {
     let event_loop = glutin::event_loop::EventLoop::new();
@@ -31,49 +26,15 @@ fn main() {
         w.window().set_cursor_visible(false);
     }
 
-    let vertex_shader_src = fs::read_to_string("assets/shaders/vertex_shader.glsl").unwrap();
-    let fragment_shader_src = fs::read_to_string("assets/shaders/fragment_shader.glsl").unwrap();
-
-    let program = Rc::new(glium::Program::from_source(&display,
-        &vertex_shader_src.to_string(),
-        &fragment_shader_src.to_string(), None).unwrap());
-
-    // objects
-
-    let mut board = {
-        let object = {
-            let source = fs::read_to_string("assets/models/board.obj").unwrap();
-            obj::parse(source).unwrap()
-        };
-        let mut mesh = Mesh::new(&object.objects[0], &display);
-
-        let material1 = Material {
-            albedo: Color::new(0.075, 0.04, 0.01, 1.0),
-            shader: program.clone(),
-        };
-        let material2 = Material {
-            albedo: Color::new(1.0, 1.0, 1.0, 1.0),
-            shader: program.clone(),
-        };
-        mesh.materials = vec!(material1.clone(), material2, material1);
-        Object3D::new(mesh)
-    };
-    board.mesh.transform.scale(Vector3::fill(0.1));
-    board.mesh.transform.translate(Vector3::new(0.0, -0.2, 0.0));
-
-    let mut rook = {
-        let object = {
-            let source = fs::read_to_string("assets/models/rook.obj").unwrap();
-            obj::parse(source).unwrap()
-        };
-        let mut mesh = Mesh::new(&object.objects[0], &display);
-        mesh.materials = vec!(Material {
-            albedo: Color::new(0.7, 0.3, 0.1, 1.0),
-            shader: program.clone(),
-        });
-        Object3D::new(mesh)
+    let mut assets = AssetManager::new(&display, "assets");
+    assets.set_mesh_cache(Some(PathBuf::from("assets/.cache")));
+    let mut scene = match Scene::load("scenes/chess.ron", &mut assets) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        },
     };
-    rook.mesh.transform.scale(Vector3::fill(0.1));
 
     // variables
 
@@ -81,15 +42,12 @@ fn main() {
     let speed: f32 = 0.5;
 
     let mut movement_buttons = [false; 6];
-    let mut view_pos = Vector3::new(0.0, 0.0, -2.0);
-    let mut view_up = Vector3::new(0.0, 1.0, 0.0);
-
-    let mut yaw = -90.0;
-    let mut pitch = 0.0;
+    let mut view_pos = scene.camera.transform.get_position();
+    let mut view_rotation = scene.camera.transform.get_quaternion();
 
     let mut elapsed_time: f32 = 0.0;
 
-    let mut mouse_position = (0.0f32, 0.0f32);
+    let mut mouse_position = Vector2::default();
 
     // =======================
     // ====== loop ===========
@@ -117,7 +75,7 @@ fn main() {
                     }
                 },
                 event::WindowEvent::CursorMoved { position: e, .. } => {
-                    mouse_position = (e.x as f32, e.y as f32);
+                    mouse_position = Vector2::new(e.x as f32, e.y as f32);
                 }
                 _ => return,
             },
@@ -132,7 +90,7 @@ fn main() {
         // update
 
         let frame_size = display.get_framebuffer_dimensions();
-        let mut center = ((frame_size.0 / 2) as f32, (frame_size.1 / 2) as f32);
+        let center = Vector2::new((frame_size.0 / 2) as f32, (frame_size.1 / 2) as f32);
 
         let params = glium::DrawParameters {
             depth: glium::Depth {
@@ -144,29 +102,20 @@ fn main() {
         };
 
         let sensitivity: f32 = 0.025;
-        let mouse_offset = (
-            (mouse_position.0 - center.0) * sensitivity,
-            (center.1 - mouse_position.1) * sensitivity);
-
-        yaw += mouse_offset.0;
-        pitch += mouse_offset.1;
-
-        if pitch > 89.0 {
-            pitch =  89.0;
-        }
-        if pitch < -89.0 {
-            pitch = -89.0;
-        }
+        let mouse_offset = Vector2::new(
+            mouse_position.x - center.x,
+            center.y - mouse_position.y) * sensitivity;
 
-        let pitch = pitch * (std::f32::consts::PI / 180.0);
-        let yaw = yaw * (std::f32::consts::PI / 180.0);
-
-        let direction = Vector3::new(
-            pitch.cos() * yaw.cos(),
-            pitch.sin(),
-            -pitch.cos() * yaw.sin(),
-        ).normalized();
+        // yaw around the world up, pitch around the camera right.
+        // pitch stays within 89 degrees of the horizon so the view can't flip over
+        let elevation = view_rotation.xform(Vector3::new(0.0, 0.0, 1.0)).y.clamp(-1.0, 1.0).asin().to_degrees();
+        let pitch_offset = (elevation + mouse_offset.y).clamp(-89.0, 89.0) - elevation;
+        let yaw = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), mouse_offset.x.to_radians());
+        let pitch = Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), -pitch_offset.to_radians());
+        view_rotation = (yaw * view_rotation * pitch).normalized();
 
+        let direction = view_rotation.xform(Vector3::new(0.0, 0.0, 1.0));
+        let view_up = view_rotation.xform(Vector3::new(0.0, 1.0, 0.0));
 
         let delta = elapsed_time * 2.0;
         let view_right = direction.cross(view_up).normalized();
@@ -177,33 +126,44 @@ fn main() {
         if movement_buttons[4] { view_pos.y -= delta };
         if movement_buttons[5] { view_pos.y += delta };
 
-        let view = math::view_matrix(
-            view_pos,
-            direction,
-            view_up);
+        scene.camera.transform.set_position(view_pos);
+        scene.camera.transform.set_quaternion(view_rotation);
+
+        let default_light = [Light::new(Vector3::new(1.4, 0.4, -0.7), Color::new(1.0, 1.0, 1.0, 1.0))];
+        let lights = if scene.lights.is_empty() { &default_light[..] } else { &scene.lights[..] };
 
         let mut renderer = Renderer::new(&display,
-            [0.0, 0.0, 0.0],
-            [1.4, 0.4, -0.7f32],
-            view,
-            math::perspective_matrix(frame_size, 3.141592 / 3.0, 1024.0, 0.1),
+            view_pos.as_array(),
+            lights,
+            scene.camera.view_matrix(),
+            scene.camera.perspective_matrix(frame_size),
             params,
         );
 
-        rook.mesh.transform.set_position(Vector3::new(angle.sin(), 0.0, angle.cos())
-            * rook.mesh.transform.get_scale().z * 3.0);
+        // scenes without a rook are shown without the animation
+        if let Some(rook) = scene.root.find_mut("rook") {
+            let rook = rook.get_transform_mut();
+            rook.set_rotation(Vector3::new(0.0, angle, 0.0));
+            rook.set_position(Vector3::new(angle.sin() * 2.0, 0.2, angle.cos() * 2.0));
+        }
         angle += speed * elapsed_time;
 
+        scene.update();
+
         let w = display.gl_window();
         let window = w.window();
-        window.set_cursor_position(glutin::dpi::PhysicalPosition { x: center.0, y: center.1 });
+        window.set_cursor_position(glutin::dpi::PhysicalPosition { x: center.x, y: center.y }).ok();
 
         // draw
 
         renderer.clear(Color::new(0.02, 0.02, 0.02, 1.0));
-        renderer.draw(&rook.mesh);
-        renderer.draw(&board.mesh);
-        renderer.show();
+        let drawn = renderer.draw_scene(&scene.root);
+        // the frame is finished even after a failed draw, glium panics when an unfinished frame is dropped
+        let shown = renderer.show();
+        for e in drawn.err().into_iter().chain(shown.err()) {
+            eprintln!("{}", e);
+            *control_flow = glutin::event_loop::ControlFlow::Exit;
+        }
 
         elapsed_time = frame_time.elapsed().as_secs_f32();
     });